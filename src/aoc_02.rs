@@ -1,7 +1,15 @@
-use crate::{opcode_computer::OpcodeComputer, read_input::read_intcode_program};
+use crate::{
+    opcode_computer::OpcodeComputer,
+    read_input::{read_intcode_program, read_patch},
+};
 
 pub fn aoc_02_01() -> isize {
-    init_with_noun_verb(12, 2).run()
+    let mut computer = OpcodeComputer::new(&read_intcode_program(2));
+    read_patch(2, "1202 program alarm")
+        .apply(&mut computer)
+        .unwrap();
+    computer.run();
+    computer.first_cell()
 }
 
 pub fn aoc_02_02() -> isize {
@@ -9,7 +17,7 @@ pub fn aoc_02_02() -> isize {
 
     for noun in 0..100 {
         for verb in 0..100 {
-            if init_with_noun_verb(noun, verb).run() == looking_for {
+            if run_with_noun_verb(noun, verb) == looking_for {
                 return noun * 100 + verb;
             }
         }
@@ -18,10 +26,10 @@ pub fn aoc_02_02() -> isize {
     return 0;
 }
 
-fn init_with_noun_verb(noun: isize, verb: isize) -> OpcodeComputer {
-    let mut memory = read_intcode_program(2);
-    memory[1] = noun;
-    memory[2] = verb;
-
-    OpcodeComputer::new(&memory)
+fn run_with_noun_verb(noun: isize, verb: isize) -> isize {
+    let mut computer = OpcodeComputer::new(&read_intcode_program(2));
+    computer.poke(1, noun).unwrap();
+    computer.poke(2, verb).unwrap();
+    computer.run();
+    computer.first_cell()
 }
@@ -1,4 +1,7 @@
-use crate::{opcode_computer::OpcodeComputer, read_input::read_intcode_program};
+use {
+    crate::{opcode_computer::OpcodeComputer, read_input::read_intcode_program},
+    std::thread,
+};
 
 pub fn aoc_07_01() -> isize {
     let program = read_intcode_program(7);
@@ -13,147 +16,239 @@ pub fn aoc_07_02() -> isize {
     signal
 }
 
-fn find_max_signal(program: &Vec<isize>, phases: &Vec<isize>) -> (isize, Vec<isize>) {
-    let mut optimal_phases = phases.clone();
-    let mut signal = 0;
-
-    let mut all_phases = swap_4(&optimal_phases);
-    all_phases.insert(0, optimal_phases.clone());
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchOptions {
+    pub chain_length: Option<usize>,
+    pub pruning: bool,
+    pub threads: usize,
+}
 
-    for phases in all_phases.clone() {
-        let new_signal = amplifier(&phases, &program);
-        if new_signal > signal {
-            signal = new_signal;
-            optimal_phases = phases;
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            chain_length: None,
+            pruning: false,
+            threads: 1,
         }
     }
+}
 
-    (signal, optimal_phases)
+fn find_max_signal(program: &Vec<isize>, phases: &Vec<isize>) -> (isize, Vec<isize>) {
+    let options = SearchOptions {
+        pruning: true,
+        ..SearchOptions::default()
+    };
+
+    rank_phase_settings(program, phases, &options)
+        .into_iter()
+        .next()
+        .expect("No phase setting produces a signal")
 }
 
-fn swap_4(seq: &Vec<isize>) -> Vec<Vec<isize>> {
-    let mut seqs = vec![];
-
-    let mut new_seqs = swap_3(seq);
-    let seq = new_seqs.last().unwrap().clone();
-    seqs.append(&mut new_seqs);
-
-    let mut seq = seq.clone();
-    seq.swap(0, 4);
-    seqs.push(seq.clone());
-    let mut new_seqs = swap_3(&seq);
-    let seq = new_seqs.last().unwrap().clone();
-    seqs.append(&mut new_seqs);
-
-    let mut seq = seq.clone();
-    seq.swap(0, 4);
-    seqs.push(seq.clone());
-    let mut new_seqs = swap_3(&seq);
-    let seq = new_seqs.last().unwrap().clone();
-    seqs.append(&mut new_seqs);
-
-    let mut seq = seq.clone();
-    seq.swap(0, 4);
-    seqs.push(seq.clone());
-    let mut new_seqs = swap_3(&seq);
-    let seq = new_seqs.last().unwrap().clone();
-    seqs.append(&mut new_seqs);
-
-    let mut seq = seq.clone();
-    seq.swap(0, 4);
-    seqs.push(seq.clone());
-    let mut new_seqs = swap_3(&seq);
-    seqs.append(&mut new_seqs);
-
-    seqs
+// Settings for which some amplifier stops without producing a signal are left out.
+// Work is split between threads by the phase of the first amplifier.
+pub fn rank_phase_settings(
+    program: &[isize],
+    phases: &[isize],
+    options: &SearchOptions,
+) -> Vec<(isize, Vec<isize>)> {
+    let length = options.chain_length.unwrap_or(phases.len());
+    if length == 0 || length > phases.len() {
+        panic!(
+            "Cannot build a chain of {} amplifiers from {} phases",
+            length,
+            phases.len()
+        );
+    }
+    let threads = options.threads.max(1);
+
+    let mut ranking: Vec<(isize, Vec<isize>)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|worker| {
+                scope.spawn(move || {
+                    (worker..phases.len())
+                        .step_by(threads)
+                        .flat_map(|first| match options.pruning {
+                            true => search_pruned(program, phases, first, length),
+                            false => search_all(program, phases, first, length),
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+
+    ranking.sort_by(|(signal_a, phases_a), (signal_b, phases_b)| {
+        signal_b.cmp(signal_a).then(phases_a.cmp(phases_b))
+    });
+    ranking
 }
 
-fn swap_3(seq: &Vec<isize>) -> Vec<Vec<isize>> {
-    let mut seqs = vec![];
-
-    let mut new_seqs = swap_2(seq);
-    let seq = new_seqs.last().unwrap().clone();
-    seqs.append(&mut new_seqs);
-
-    let mut seq = seq.clone();
-    seq.swap(1, 3);
-    seqs.push(seq.clone());
-    let mut new_seqs = swap_2(&seq);
-    let seq = new_seqs.last().unwrap().clone();
-    seqs.append(&mut new_seqs);
-
-    let mut seq = seq.clone();
-    seq.swap(1, 3);
-    seqs.push(seq.clone());
-    let mut new_seqs = swap_2(&seq);
-    let seq = new_seqs.last().unwrap().clone();
-    seqs.append(&mut new_seqs);
-
-    let mut seq = seq.clone();
-    seq.swap(0, 3);
-    seqs.push(seq.clone());
-    let mut new_seqs = swap_2(&seq);
-    seqs.append(&mut new_seqs);
-
-    seqs
+fn search_all(
+    program: &[isize],
+    phases: &[isize],
+    first: usize,
+    length: usize,
+) -> Vec<(isize, Vec<isize>)> {
+    let mut rest = phases.to_vec();
+    let first = rest.remove(first);
+
+    combinations(&rest, length - 1)
+        .iter()
+        .flat_map(|combination| permutations(combination))
+        .filter_map(|permutation| {
+            let mut setting = vec![first];
+            setting.extend(permutation);
+            amplifier(&setting, program).map(|signal| (signal, setting))
+        })
+        .collect()
 }
 
-fn swap_2(seq: &Vec<isize>) -> Vec<Vec<isize>> {
-    let mut seqs = vec![];
+// The first signal an amplifier produces only depends on the phases before it, so
+// every prefix is run once and prefixes that stop without a signal are cut off.
+fn search_pruned(
+    program: &[isize],
+    phases: &[isize],
+    first: usize,
+    length: usize,
+) -> Vec<(isize, Vec<isize>)> {
+    let mut rest = phases.to_vec();
+    let first = rest.remove(first);
+    let mut ranking = vec![];
+
+    if let Some(chain) = Chain::default().extend(program, first) {
+        chain.search(program, &rest, length, &mut ranking);
+    }
+    ranking
+}
+
+#[derive(Default)]
+struct Chain {
+    computers: Vec<OpcodeComputer>,
+    phases: Vec<isize>,
+    signal: isize,
+}
 
-    let mut seq = seq.clone();
-    seq.swap(0, 1);
-    seqs.push(seq.clone());
+impl Chain {
+    fn extend(&self, program: &[isize], phase: isize) -> Option<Self> {
+        let mut computer = OpcodeComputer::new(&program.to_vec());
+        computer.add_input(&phase).add_input(&self.signal).run();
+        let signal = computer.get_output()?;
+
+        let mut computers: Vec<OpcodeComputer> =
+            self.computers.iter().map(OpcodeComputer::fork).collect();
+        computers.push(computer);
+        let mut phases = self.phases.clone();
+        phases.push(phase);
+
+        Some(Self {
+            computers,
+            phases,
+            signal,
+        })
+    }
+
+    fn search(
+        mut self,
+        program: &[isize],
+        rest: &[isize],
+        length: usize,
+        ranking: &mut Vec<(isize, Vec<isize>)>,
+    ) {
+        if self.phases.len() == length {
+            if let Some(signal) = feedback_loop(&mut self.computers, self.signal) {
+                ranking.push((signal, self.phases));
+            }
+            return;
+        }
 
-    let mut seq = seq.clone();
-    seq.swap(1, 2);
-    seqs.push(seq.clone());
+        for index in 0..rest.len() {
+            if let Some(chain) = self.extend(program, rest[index]) {
+                let mut remaining = rest.to_vec();
+                remaining.remove(index);
+                chain.search(program, &remaining, length, ranking);
+            }
+        }
+    }
+}
 
-    let mut seq = seq.clone();
-    seq.swap(0, 1);
-    seqs.push(seq.clone());
+// Heap's algorithm
+pub fn permutations(items: &[isize]) -> Vec<Vec<isize>> {
+    let mut items = items.to_vec();
+    let mut counters = vec![0; items.len()];
+    let mut permutations = vec![items.clone()];
+
+    let mut index = 1;
+    while index < items.len() {
+        if counters[index] < index {
+            let other = if index % 2 == 0 { 0 } else { counters[index] };
+            items.swap(other, index);
+            permutations.push(items.clone());
+            counters[index] += 1;
+            index = 1;
+        } else {
+            counters[index] = 0;
+            index += 1;
+        }
+    }
 
-    let mut seq = seq.clone();
-    seq.swap(1, 2);
-    seqs.push(seq.clone());
+    permutations
+}
 
-    let mut seq = seq.clone();
-    seq.swap(0, 1);
-    seqs.push(seq);
+fn combinations(items: &[isize], size: usize) -> Vec<Vec<isize>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    if items.len() < size {
+        return vec![];
+    }
 
-    seqs
+    let mut with_first: Vec<Vec<isize>> = combinations(&items[1..], size - 1)
+        .into_iter()
+        .map(|mut combination| {
+            combination.insert(0, items[0]);
+            combination
+        })
+        .collect();
+    with_first.extend(combinations(&items[1..], size));
+    with_first
 }
 
-fn amplifier(phase_settings: &Vec<isize>, program: &Vec<isize>) -> isize {
+fn amplifier(phase_settings: &[isize], program: &[isize]) -> Option<isize> {
     let mut comps = vec![];
 
     // Setup
     for phase in phase_settings {
-        let mut comp = OpcodeComputer::new(&program);
+        let mut comp = OpcodeComputer::new(&program.to_vec());
         comp.add_input(&phase);
         comps.push(comp);
     }
 
-    // Run comps
+    feedback_loop(&mut comps, 0)
+}
+
+fn feedback_loop(comps: &mut [OpcodeComputer], mut input: isize) -> Option<isize> {
     let mut index: usize = 0;
-    let mut input: isize = 0;
     while !comps.iter().all(|comp| comp.halted()) {
-        if index >= phase_settings.len() {
+        if index >= comps.len() {
             index = 0;
         }
         comps[index].add_input(&input).run();
-        input = comps[index]
-            .get_output()
-            .expect(&format!("There's no output for: {:?}", comps[index]));
+        input = comps[index].get_output()?;
         index += 1;
     }
 
-    input
+    Some(input)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {super::*, crate::compiler::compile};
 
     #[test]
     fn runs_first_example_program() {
@@ -164,7 +259,7 @@ mod tests {
         let max_signal = 43210;
 
         let signal = amplifier(&phase_settings, &program);
-        assert_eq!(signal, max_signal);
+        assert_eq!(signal, Some(max_signal));
     }
 
     #[test]
@@ -177,7 +272,7 @@ mod tests {
         let max_signal = 54321;
 
         let signal = amplifier(&phase_settings, &program);
-        assert_eq!(signal, max_signal);
+        assert_eq!(signal, Some(max_signal));
     }
 
     #[test]
@@ -190,7 +285,7 @@ mod tests {
         let max_signal = 65210;
 
         let signal = amplifier(&phase_settings, &program);
-        assert_eq!(signal, max_signal);
+        assert_eq!(signal, Some(max_signal));
     }
 
     #[test]
@@ -244,7 +339,7 @@ mod tests {
         let max_signal = 139629729;
 
         let signal = amplifier(&phase_settings, &program);
-        assert_eq!(signal, max_signal);
+        assert_eq!(signal, Some(max_signal));
     }
 
     #[test]
@@ -258,7 +353,7 @@ mod tests {
         let max_signal = 18216;
 
         let signal = amplifier(&phase_settings, &program);
-        assert_eq!(signal, max_signal);
+        assert_eq!(signal, Some(max_signal));
     }
 
     #[test]
@@ -289,4 +384,107 @@ mod tests {
         let found = find_max_signal(&program, &phases);
         assert_eq!(found, (max_signal, phase_settings));
     }
+
+    #[test]
+    fn generates_all_permutations() {
+        let mut found = permutations(&[1, 2, 3, 4]);
+        assert_eq!(found.len(), 24);
+        assert_eq!(found[0], vec![1, 2, 3, 4]);
+
+        found.sort();
+        found.dedup();
+        assert_eq!(found.len(), 24);
+        assert_eq!(permutations(&[7]), vec![vec![7]]);
+    }
+
+    #[test]
+    fn ranks_every_phase_setting() {
+        let program = vec![
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ];
+        let ranking = rank_phase_settings(&program, &[0, 1, 2, 3, 4], &SearchOptions::default());
+
+        assert_eq!(ranking.len(), 120);
+        assert_eq!(ranking[0], (43210, vec![4, 3, 2, 1, 0]));
+        assert_eq!(ranking[119], (1234, vec![0, 1, 2, 3, 4]));
+        assert!(ranking.windows(2).all(|pair| pair[0].0 >= pair[1].0));
+    }
+
+    #[test]
+    fn searches_shorter_chains_over_larger_phase_sets() {
+        let program = vec![
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ];
+        let phases = [0, 1, 2, 3, 4, 5, 6];
+        let options = SearchOptions {
+            chain_length: Some(3),
+            ..SearchOptions::default()
+        };
+        let ranking = rank_phase_settings(&program, &phases, &options);
+
+        assert_eq!(ranking.len(), 7 * 6 * 5);
+        assert_eq!(ranking[0], (654, vec![6, 5, 4]));
+    }
+
+    #[test]
+    fn pruning_and_threads_keep_the_ranking() {
+        // Odd phases make the amplifier halt without a signal
+        let program = compile(
+            "fn main() {
+                let phase = read();
+                let signal = read();
+                if phase % 2 == 0 { print(signal * 10 + phase); }
+            }",
+        )
+        .unwrap();
+        let phases = [0, 1, 2, 3, 4, 5, 6];
+        let options = SearchOptions {
+            chain_length: Some(3),
+            ..SearchOptions::default()
+        };
+
+        let full = rank_phase_settings(&program, &phases, &options);
+        let pruned = rank_phase_settings(
+            &program,
+            &phases,
+            &SearchOptions {
+                pruning: true,
+                threads: 3,
+                ..options
+            },
+        );
+
+        assert_eq!(full.len(), 4 * 3 * 2);
+        assert_eq!(full[0], (642, vec![6, 4, 2]));
+        assert_eq!(pruned, full);
+    }
+
+    #[test]
+    fn pruned_search_follows_feedback_loops() {
+        let program = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let phases = [5, 6, 7, 8, 9];
+        let pruned = SearchOptions {
+            pruning: true,
+            threads: 2,
+            ..SearchOptions::default()
+        };
+
+        assert_eq!(
+            rank_phase_settings(&program, &phases, &pruned),
+            rank_phase_settings(&program, &phases, &SearchOptions::default())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot build a chain of 4 amplifiers from 3 phases")]
+    fn refuses_chain_longer_than_phase_set() {
+        let options = SearchOptions {
+            chain_length: Some(4),
+            ..SearchOptions::default()
+        };
+        rank_phase_settings(&[99], &[0, 1, 2], &options);
+    }
 }
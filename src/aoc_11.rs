@@ -1,5 +1,8 @@
 use {
-    crate::{opcode_computer::OpcodeComputer, read_input::read_intcode_program},
+    crate::{
+        opcode_computer::{ComputerState, OpcodeComputer, StopReason},
+        read_input::read_intcode_program,
+    },
     std::collections::HashMap,
 };
 
@@ -149,7 +152,9 @@ impl PaintingRobot {
     fn next(&mut self) {
         let input_color = self.map.get(&self.coordinate).unwrap_or(&Color::Black);
         self.computer.add_input(&input_color.to_int());
-        self.computer.run();
+        if self.computer.run_until_output(2) != StopReason::OutputProduced {
+            return;
+        }
 
         let color = Color::from_int(&self.computer.get_output().unwrap());
         let direction = self.direction.turn(&self.computer.get_output().unwrap());
@@ -160,7 +165,28 @@ impl PaintingRobot {
         self.coordinate = coordinate;
     }
 
+    // Between moves the computer is running, anything else means it halted, faulted or
+    // asked for more input than the camera gives
     fn done(&self) -> bool {
-        self.computer.halted()
+        !matches!(
+            self.computer.state(),
+            ComputerState::Initialized | ComputerState::Running
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stops_on_faulted_or_starved_computer() {
+        for program in &[vec![104, 1, 4, -1, 99], vec![3, 0, 3, 0, 99]] {
+            let mut robot = PaintingRobot::new(program, &(0, 0), Color::Black);
+            robot.next();
+
+            assert!(robot.done());
+            assert_eq!(robot.coordinate, (0, 0));
+        }
     }
 }
@@ -1,9 +1,14 @@
 use {
-    crate::{opcode_computer::OpcodeComputer, read_input::read_intcode_program},
+    crate::{
+        input_recording::{replay, Recording},
+        memory_scan::MemoryScan,
+        opcode_computer::OpcodeComputer,
+        read_input::{read_intcode_program, read_patch},
+    },
     pancurses::{
         cbreak, curs_set, endwin, initscr, noecho, resize_term, start_color, Input, Window,
     },
-    std::cmp::max,
+    std::{cell::RefCell, cmp::max, collections::HashMap, rc::Rc},
 };
 
 pub fn aoc_13_01() -> usize {
@@ -19,10 +24,20 @@ pub fn aoc_13_01() -> usize {
 }
 
 pub fn aoc_13_02() -> isize {
-    let mut program = read_intcode_program(13);
-    program[0] = 2;
-    let mut computer = OpcodeComputer::new(&program);
+    play(&mut free_play())
+}
+
+// Plays the game like `aoc_13_02` and returns the session for replaying it later
+pub fn record() -> Recording {
+    let mut computer = free_play();
+    let recording = Rc::new(RefCell::new(Recording::default()));
+    computer.add_observer(recording.clone());
+    play(&mut computer);
+
+    recording.replace(Recording::default())
+}
 
+fn play(computer: &mut OpcodeComputer) -> isize {
     let window = initscr();
     resize_term(35, 40);
     start_color();
@@ -71,6 +86,83 @@ pub fn aoc_13_02() -> isize {
     game.score
 }
 
+pub fn autoplay() -> Recording {
+    let mut computer = free_play();
+    let recording = Rc::new(RefCell::new(Recording::default()));
+    computer.add_observer(recording.clone()).run();
+    let mut game = Game::from_output(&computer.get_all_output());
+
+    while !computer.halted() {
+        computer.add_input(&game.joystick()).run();
+        game.update_state(&computer.get_all_output());
+    }
+
+    recording.replace(Recording::default())
+}
+
+pub fn replay_session(recording: &Recording) -> (isize, usize) {
+    let mut computer = free_play();
+    replay(&mut computer, recording);
+
+    let (pixels, score) = parse_output(&computer.get_all_output());
+    let screen: HashMap<Coord, TileType> = pixels
+        .into_iter()
+        .map(|pixel| (pixel.coord, pixel.tile_type))
+        .collect();
+    let blocks_left = screen
+        .values()
+        .filter(|tile_type| **tile_type == TileType::Block)
+        .count();
+
+    (score.unwrap_or(0), blocks_left)
+}
+
+fn free_play() -> OpcodeComputer {
+    let mut computer = OpcodeComputer::new(&read_intcode_program(13));
+    read_patch(13, "free play").apply(&mut computer).unwrap();
+    computer
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GameVariables {
+    pub score: Option<usize>,
+    pub ball_x: Option<usize>,
+    pub paddle_x: Option<usize>,
+}
+
+pub fn locate_game_variables(max_frames: usize) -> GameVariables {
+    let mut computer = free_play();
+    computer.run();
+    let mut game = Game::from_output(&computer.get_all_output());
+
+    let mut score = MemoryScan::default();
+    let mut ball_x = MemoryScan::default();
+    let mut paddle_x = MemoryScan::default();
+
+    for _ in 0..max_frames {
+        let snapshot = computer.snapshot();
+        score.observe_equal(&snapshot, game.score);
+        ball_x.observe_equal(&snapshot, game.tile_x(TileType::Ball));
+        paddle_x.observe_equal(&snapshot, game.tile_x(TileType::Paddle));
+
+        let found = [&score, &ball_x, &paddle_x]
+            .iter()
+            .all(|scan| scan.found().is_some());
+        if found || computer.halted() {
+            break;
+        }
+
+        computer.add_input(&game.joystick()).run();
+        game.update_state(&computer.get_all_output());
+    }
+
+    GameVariables {
+        score: score.found(),
+        ball_x: ball_x.found(),
+        paddle_x: paddle_x.found(),
+    }
+}
+
 fn parse_output(seq: &Vec<isize>) -> (Vec<Pixel>, Option<isize>) {
     let mut pixels = vec![];
     let mut score = None;
@@ -165,6 +257,18 @@ impl Game {
         }
     }
 
+    fn tile_x(&self, tile_type: TileType) -> isize {
+        self.pixels
+            .iter()
+            .find(|pixel| pixel.tile_type == tile_type)
+            .map(|pixel| pixel.coord.0)
+            .unwrap()
+    }
+
+    fn joystick(&self) -> isize {
+        (self.tile_x(TileType::Ball) - self.tile_x(TileType::Paddle)).signum()
+    }
+
     fn draw_update(&mut self, window: &Window) {
         window.mvaddstr(self.height as i32, 7, format!("{}          ", self.score));
 
@@ -221,3 +325,32 @@ impl TileType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::read_input::read_recording};
+
+    #[test]
+    fn replays_recorded_winning_game() {
+        assert_eq!(replay_session(&read_recording(13)), (13331, 0));
+    }
+
+    #[test]
+    fn autoplay_matches_recorded_game() {
+        assert_eq!(autoplay(), read_recording(13));
+    }
+
+    #[test]
+    fn locates_score_ball_and_paddle_cells() {
+        let variables = locate_game_variables(1000);
+
+        let mut computer = OpcodeComputer::new(&read_intcode_program(13));
+        computer.run();
+        let game = Game::from_output(&computer.get_all_output());
+        let peek = |address: Option<usize>| computer.peek(address.unwrap() as isize).unwrap();
+
+        assert_eq!(peek(variables.score), 0);
+        assert_eq!(peek(variables.ball_x), game.tile_x(TileType::Ball));
+        assert_eq!(peek(variables.paddle_x), game.tile_x(TileType::Paddle));
+    }
+}
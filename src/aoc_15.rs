@@ -0,0 +1,284 @@
+use {
+    crate::{
+        opcode_computer::{OpcodeComputer, StopReason},
+        read_input::read_intcode_program,
+    },
+    std::collections::{HashMap, VecDeque},
+};
+
+pub fn aoc_15_01() -> usize {
+    let map = explore(&mut Droid::new(&read_intcode_program(15)));
+
+    distances(&map, &(0, 0))[&map.oxygen_system()]
+}
+
+pub fn aoc_15_02() -> usize {
+    let map = explore(&mut Droid::new(&read_intcode_program(15)));
+
+    fill_time(&map)
+}
+
+type Coord = (isize, isize);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Direction {
+    North,
+    South,
+    West,
+    East,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::South,
+    Direction::West,
+    Direction::East,
+];
+
+impl Direction {
+    fn to_command(self) -> isize {
+        use Direction::*;
+
+        match self {
+            North => 1,
+            South => 2,
+            West => 3,
+            East => 4,
+        }
+    }
+
+    fn opposite(&self) -> Self {
+        use Direction::*;
+
+        match self {
+            North => South,
+            South => North,
+            West => East,
+            East => West,
+        }
+    }
+
+    fn next_coordinate(&self, (x, y): &Coord) -> Coord {
+        use Direction::*;
+
+        match self {
+            North => (*x, y - 1),
+            South => (*x, y + 1),
+            West => (x - 1, *y),
+            East => (x + 1, *y),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tile {
+    Wall,
+    Open,
+    OxygenSystem,
+}
+
+impl Tile {
+    fn from_status(status: isize) -> Self {
+        match status {
+            0 => Tile::Wall,
+            1 => Tile::Open,
+            2 => Tile::OxygenSystem,
+            _ => unreachable!("Unknown status {}", status),
+        }
+    }
+}
+
+trait Remote {
+    fn move_droid(&mut self, direction: Direction) -> Tile;
+}
+
+struct Droid {
+    computer: OpcodeComputer,
+}
+
+impl Droid {
+    fn new(program: &Vec<isize>) -> Self {
+        Self {
+            computer: OpcodeComputer::new(program),
+        }
+    }
+}
+
+impl Remote for Droid {
+    fn move_droid(&mut self, direction: Direction) -> Tile {
+        self.computer.add_input(&direction.to_command());
+        if self.computer.run_until_output(1) != StopReason::OutputProduced {
+            panic!("Droid stopped responding");
+        }
+
+        Tile::from_status(self.computer.get_output().unwrap())
+    }
+}
+
+struct Map {
+    tiles: HashMap<Coord, Tile>,
+}
+
+impl Map {
+    fn oxygen_system(&self) -> Coord {
+        *self
+            .tiles
+            .iter()
+            .find(|(_, tile)| **tile == Tile::OxygenSystem)
+            .expect("Oxygen system was not found")
+            .0
+    }
+}
+
+// The droid starts at (0, 0) and walks every corridor, stepping back after each dead end
+fn explore(remote: &mut dyn Remote) -> Map {
+    let mut tiles = HashMap::new();
+    tiles.insert((0, 0), Tile::Open);
+    explore_from(remote, &(0, 0), &mut tiles);
+
+    Map { tiles }
+}
+
+fn explore_from(remote: &mut dyn Remote, position: &Coord, tiles: &mut HashMap<Coord, Tile>) {
+    for direction in DIRECTIONS.iter() {
+        let next = direction.next_coordinate(position);
+        if tiles.contains_key(&next) {
+            continue;
+        }
+
+        let tile = remote.move_droid(*direction);
+        tiles.insert(next, tile);
+        if tile != Tile::Wall {
+            explore_from(remote, &next, tiles);
+            remote.move_droid(direction.opposite());
+        }
+    }
+}
+
+fn distances(map: &Map, from: &Coord) -> HashMap<Coord, usize> {
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+    distances.insert(*from, 0);
+    queue.push_back(*from);
+
+    while let Some(position) = queue.pop_front() {
+        let distance = distances[&position];
+        for direction in DIRECTIONS.iter() {
+            let next = direction.next_coordinate(&position);
+            let open = map.tiles.get(&next).is_some_and(|tile| *tile != Tile::Wall);
+            if open && !distances.contains_key(&next) {
+                distances.insert(next, distance + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    distances
+}
+
+// Oxygen spreads to every neighbour each minute, so the fill time is the longest
+// distance from the oxygen system
+fn fill_time(map: &Map) -> usize {
+    distances(map, &map.oxygen_system())
+        .values()
+        .cloned()
+        .max()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MazeRemote {
+        tiles: HashMap<Coord, Tile>,
+        position: Coord,
+    }
+
+    impl MazeRemote {
+        // `D` marks the droid's start, anything outside the text is a wall
+        fn parse(text: &str) -> Self {
+            let mut tiles = HashMap::new();
+            let mut position = (0, 0);
+
+            for (y, line) in text.lines().enumerate() {
+                for (x, c) in line.chars().enumerate() {
+                    let coord = (x as isize, y as isize);
+                    let tile = match c {
+                        '.' => Tile::Open,
+                        'O' => Tile::OxygenSystem,
+                        'D' => {
+                            position = coord;
+                            Tile::Open
+                        }
+                        _ => Tile::Wall,
+                    };
+                    tiles.insert(coord, tile);
+                }
+            }
+
+            let (dx, dy) = position;
+            let tiles = tiles
+                .into_iter()
+                .map(|((x, y), tile)| ((x - dx, y - dy), tile))
+                .collect();
+            Self {
+                tiles,
+                position: (0, 0),
+            }
+        }
+    }
+
+    impl Remote for MazeRemote {
+        fn move_droid(&mut self, direction: Direction) -> Tile {
+            let next = direction.next_coordinate(&self.position);
+            let tile = *self.tiles.get(&next).unwrap_or(&Tile::Wall);
+            if tile != Tile::Wall {
+                self.position = next;
+            }
+            tile
+        }
+    }
+
+    const MAZE: &str = "\
+ ##
+#..##
+#.#..#
+#.O.#
+ ###";
+
+    #[test]
+    fn explores_whole_maze_and_returns_to_start() {
+        let mut remote = MazeRemote::parse(&MAZE.replacen("..##", "D.##", 1));
+        let map = explore(&mut remote);
+
+        let open = |tiles: &HashMap<Coord, Tile>| {
+            let mut open: Vec<Coord> = tiles
+                .iter()
+                .filter(|(_, tile)| **tile != Tile::Wall)
+                .map(|(coord, _)| *coord)
+                .collect();
+            open.sort();
+            open
+        };
+        assert_eq!(open(&map.tiles), open(&remote.tiles));
+        assert_eq!(remote.position, (0, 0));
+        assert_eq!(map.oxygen_system(), (1, 2));
+    }
+
+    #[test]
+    fn finds_shortest_path_to_oxygen_system() {
+        let mut remote = MazeRemote::parse("#####\n#D..#\n#.#.#\n#..O#\n#####");
+        let map = explore(&mut remote);
+
+        assert_eq!(distances(&map, &(0, 0))[&map.oxygen_system()], 4);
+    }
+
+    #[test]
+    fn fills_example_maze_in_four_minutes() {
+        let mut remote = MazeRemote::parse(&MAZE.replacen("..##", "D.##", 1));
+        let map = explore(&mut remote);
+
+        assert_eq!(fill_time(&map), 4);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::read_input::read_lines;
+
+const PHASES: usize = 100;
+const REPEATS: usize = 10_000;
+const MESSAGE_LENGTH: usize = 8;
+const OFFSET_LENGTH: usize = 7;
+
+pub fn aoc_16_01() -> String {
+    let signal = parse_signal(&read_lines(16)[0]);
+
+    first_digits(&fft(&signal, PHASES))
+}
+
+pub fn aoc_16_02() -> String {
+    let signal = parse_signal(&read_lines(16)[0]);
+
+    decode_message(&signal, PHASES)
+}
+
+fn parse_signal(text: &str) -> Vec<isize> {
+    text.trim()
+        .chars()
+        .map(|c| c.to_digit(10).expect("Signal must consist of digits") as isize)
+        .collect()
+}
+
+fn first_digits(signal: &[isize]) -> String {
+    signal
+        .iter()
+        .take(MESSAGE_LENGTH)
+        .map(|digit| digit.to_string())
+        .collect()
+}
+
+fn fft(signal: &[isize], phases: usize) -> Vec<isize> {
+    (0..phases).fold(signal.to_vec(), |signal, _| phase(&signal))
+}
+
+// The pattern for position n is n + 1 zeros, ones, zeros and minus ones, skipping
+// the very first zero. Each block of ones or minus ones is summed via prefix sums.
+fn phase(signal: &[isize]) -> Vec<isize> {
+    let mut prefix_sums = vec![0; signal.len() + 1];
+    for (index, value) in signal.iter().enumerate() {
+        prefix_sums[index + 1] = prefix_sums[index] + value;
+    }
+    let block_sum = |start: usize, width: usize| {
+        let end = usize::min(start + width, signal.len());
+        prefix_sums[end] - prefix_sums[start]
+    };
+
+    (1..=signal.len())
+        .map(|width| {
+            let mut sum = 0;
+            let mut start = width - 1;
+            while start < signal.len() {
+                sum += block_sum(start, width);
+                if start + 2 * width < signal.len() {
+                    sum -= block_sum(start + 2 * width, width);
+                }
+                start += 4 * width;
+            }
+            sum.abs() % 10
+        })
+        .collect()
+}
+
+// In the second half of the signal every pattern is zeros followed by ones, so each
+// digit of the next phase is the sum of all digits from it to the end.
+fn decode_message(signal: &[isize], phases: usize) -> String {
+    let offset = signal
+        .iter()
+        .take(OFFSET_LENGTH)
+        .fold(0, |offset, digit| offset * 10 + *digit as usize);
+    let length = signal.len() * REPEATS;
+    if offset < length / 2 {
+        panic!(
+            "Message offset {} is not in the second half of the signal",
+            offset
+        );
+    }
+
+    let mut tail: Vec<isize> = (offset..length)
+        .map(|index| signal[index % signal.len()])
+        .collect();
+    for _ in 0..phases {
+        let mut sum = 0;
+        for digit in tail.iter_mut().rev() {
+            sum = (sum + *digit) % 10;
+            *digit = sum;
+        }
+    }
+
+    first_digits(&tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_phases_to_small_signal() {
+        let signal = parse_signal("12345678");
+
+        assert_eq!(fft(&signal, 1), parse_signal("48226158"));
+        assert_eq!(fft(&signal, 2), parse_signal("34040438"));
+        assert_eq!(fft(&signal, 3), parse_signal("03415518"));
+        assert_eq!(fft(&signal, 4), parse_signal("01029498"));
+    }
+
+    #[test]
+    fn finds_first_digits_after_100_phases() {
+        let examples = [
+            ("80871224585914546619083218645595", "24176176"),
+            ("19617804207202209144916044189917", "73745418"),
+            ("69317163492948606335995924319873", "52432133"),
+        ];
+
+        for (signal, expected) in examples.iter() {
+            assert_eq!(first_digits(&fft(&parse_signal(signal), 100)), *expected);
+        }
+    }
+
+    #[test]
+    fn decodes_message_from_repeated_signal() {
+        let examples = [
+            ("03036732577212944063491565474664", "84462026"),
+            ("02935109699940807407585447034323", "78725270"),
+            ("03081770884921959731165446850517", "53553731"),
+        ];
+
+        for (signal, expected) in examples.iter() {
+            assert_eq!(decode_message(&parse_signal(signal), 100), *expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Message offset 12 is not in the second half of the signal")]
+    fn refuses_offset_in_first_half() {
+        decode_message(&parse_signal("0000012345678"), 100);
+    }
+}
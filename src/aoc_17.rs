@@ -0,0 +1,351 @@
+use {
+    crate::{
+        ascii_computer::AsciiComputer,
+        read_input::{read_intcode_program, read_patch},
+    },
+    std::fmt,
+};
+
+const MAX_ROUTINE_LENGTH: usize = 20;
+const FUNCTIONS: usize = 3;
+const FUNCTION_NAMES: [&str; FUNCTIONS] = ["A", "B", "C"];
+
+pub fn aoc_17_01() -> usize {
+    let mut computer = AsciiComputer::new(&read_intcode_program(17));
+    computer.run();
+
+    Scaffold::parse(&computer.read().text).alignment_parameters()
+}
+
+pub fn aoc_17_02() -> isize {
+    let mut computer = AsciiComputer::new(&read_intcode_program(17));
+    read_patch(17, "wake up")
+        .apply(computer.computer())
+        .unwrap();
+    computer.run();
+
+    let scaffold = Scaffold::parse(&computer.read().text);
+    let routines = compress(&scaffold.path()).expect("Path cannot be split into functions");
+    computer.send_line(&routines.main);
+    for function in routines.functions.iter() {
+        computer.send_line(function);
+    }
+    computer.send_line("n").run();
+
+    *computer
+        .read()
+        .values
+        .last()
+        .expect("Robot didn't report collected dust")
+}
+
+type Coord = (isize, isize);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '^' => Some(Direction::Up),
+            '>' => Some(Direction::Right),
+            'v' => Some(Direction::Down),
+            '<' => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    fn turn_right(self) -> Self {
+        use Direction::*;
+
+        match self {
+            Up => Right,
+            Right => Down,
+            Down => Left,
+            Left => Up,
+        }
+    }
+
+    fn turn_left(self) -> Self {
+        self.turn_right().turn_right().turn_right()
+    }
+
+    fn next_coordinate(self, (x, y): &Coord) -> Coord {
+        use Direction::*;
+
+        match self {
+            Up => (*x, y - 1),
+            Right => (x + 1, *y),
+            Down => (*x, y + 1),
+            Left => (x - 1, *y),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Move {
+    turn: char,
+    steps: usize,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{}", self.turn, self.steps)
+    }
+}
+
+fn render(moves: &[Move]) -> String {
+    moves
+        .iter()
+        .map(Move::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+struct Scaffold {
+    rows: Vec<Vec<char>>,
+    robot: Coord,
+    direction: Direction,
+}
+
+impl Scaffold {
+    // The camera view ends with an empty line, anything after it is a prompt
+    fn parse(view: &str) -> Self {
+        let rows: Vec<Vec<char>> = view
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .map(|line| line.chars().collect())
+            .collect();
+
+        let (robot, direction) = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter().enumerate().filter_map(move |(x, c)| {
+                    Direction::from_char(*c).map(|direction| ((x as isize, y as isize), direction))
+                })
+            })
+            .next()
+            .expect("There's no robot on the scaffold");
+
+        Self {
+            rows,
+            robot,
+            direction,
+        }
+    }
+
+    fn is_scaffold(&self, (x, y): &Coord) -> bool {
+        if *x < 0 || *y < 0 {
+            return false;
+        }
+
+        self.rows
+            .get(*y as usize)
+            .and_then(|row| row.get(*x as usize))
+            .is_some_and(|c| *c == '#' || Direction::from_char(*c).is_some())
+    }
+
+    fn alignment_parameters(&self) -> usize {
+        let directions = [
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+        ];
+        let mut sum = 0;
+
+        for (y, row) in self.rows.iter().enumerate() {
+            for x in 0..row.len() {
+                let coord = (x as isize, y as isize);
+                let intersection = self.is_scaffold(&coord)
+                    && directions
+                        .iter()
+                        .all(|direction| self.is_scaffold(&direction.next_coordinate(&coord)));
+                if intersection {
+                    sum += x * y;
+                }
+            }
+        }
+
+        sum
+    }
+
+    // Goes straight through intersections and only turns at corners
+    fn path(&self) -> Vec<Move> {
+        let mut moves = vec![];
+        let mut position = self.robot;
+        let mut direction = self.direction;
+
+        loop {
+            let (turn, turned) =
+                if self.is_scaffold(&direction.turn_left().next_coordinate(&position)) {
+                    ('L', direction.turn_left())
+                } else if self.is_scaffold(&direction.turn_right().next_coordinate(&position)) {
+                    ('R', direction.turn_right())
+                } else {
+                    return moves;
+                };
+
+            direction = turned;
+            let mut steps = 0;
+            while self.is_scaffold(&direction.next_coordinate(&position)) {
+                position = direction.next_coordinate(&position);
+                steps += 1;
+            }
+            moves.push(Move { turn, steps });
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Routines {
+    main: String,
+    functions: Vec<String>,
+}
+
+fn compress(path: &[Move]) -> Option<Routines> {
+    let mut functions = vec![];
+    let mut main = vec![];
+    if !split(path, &mut functions, &mut main) {
+        return None;
+    }
+
+    let mut functions: Vec<String> = functions.iter().map(|function| render(function)).collect();
+    functions.resize(FUNCTIONS, String::new());
+    Some(Routines {
+        main: main
+            .iter()
+            .map(|index| FUNCTION_NAMES[*index])
+            .collect::<Vec<_>>()
+            .join(","),
+        functions,
+    })
+}
+
+// Depth-first search over the ways to cover the rest of the path with the known
+// functions or a new one, as long as every routine fits into the robot's memory
+fn split<'a>(path: &'a [Move], functions: &mut Vec<&'a [Move]>, main: &mut Vec<usize>) -> bool {
+    if path.is_empty() {
+        return true;
+    }
+    if 2 * main.len() + 1 > MAX_ROUTINE_LENGTH {
+        return false;
+    }
+
+    for index in 0..functions.len() {
+        let function = functions[index];
+        if path.starts_with(function) {
+            main.push(index);
+            if split(&path[function.len()..], functions, main) {
+                return true;
+            }
+            main.pop();
+        }
+    }
+
+    if functions.len() < FUNCTIONS {
+        for length in 1..=path.len() {
+            let function = &path[..length];
+            if render(function).len() > MAX_ROUTINE_LENGTH {
+                break;
+            }
+
+            functions.push(function);
+            main.push(functions.len() - 1);
+            if split(&path[length..], functions, main) {
+                return true;
+            }
+            main.pop();
+            functions.pop();
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALIBRATION_VIEW: &str = "\
+..#..........
+..#..........
+#######...###
+#.#...#...#.#
+#############
+..#...#...#..
+..#####...^..
+
+";
+
+    const PATH_VIEW: &str = "\
+#######...#####
+#.....#...#...#
+#.....#...#...#
+......#...#...#
+......#...###.#
+......#.....#.#
+^########...#.#
+......#.#...#.#
+......#########
+........#...#..
+....#########..
+....#...#......
+....#...#......
+....#...#......
+....#####......
+
+Main:
+";
+
+    #[test]
+    fn sums_alignment_parameters() {
+        assert_eq!(Scaffold::parse(CALIBRATION_VIEW).alignment_parameters(), 76);
+    }
+
+    #[test]
+    fn finds_full_path() {
+        let scaffold = Scaffold::parse(PATH_VIEW);
+
+        assert_eq!(scaffold.robot, (0, 6));
+        assert_eq!(
+            render(&scaffold.path()),
+            "R,8,R,8,R,4,R,4,R,8,L,6,L,2,R,4,R,4,R,8,R,8,R,8,L,6,L,2"
+        );
+    }
+
+    #[test]
+    fn compresses_path_into_routines() {
+        let path = Scaffold::parse(PATH_VIEW).path();
+        let routines = compress(&path).unwrap();
+
+        let expanded: Vec<&str> = routines
+            .main
+            .split(',')
+            .map(|name| {
+                let index = FUNCTION_NAMES.iter().position(|n| *n == name).unwrap();
+                routines.functions[index].as_str()
+            })
+            .collect();
+        assert_eq!(expanded.join(","), render(&path));
+        assert!(routines.main.len() <= MAX_ROUTINE_LENGTH);
+        assert!(routines
+            .functions
+            .iter()
+            .all(|function| function.len() <= MAX_ROUTINE_LENGTH));
+        assert_eq!(routines.functions.len(), FUNCTIONS);
+    }
+
+    #[test]
+    fn gives_up_on_path_without_repetitions() {
+        let path: Vec<Move> = (10..=22).map(|steps| Move { turn: 'L', steps }).collect();
+
+        assert_eq!(compress(&path), None);
+    }
+}
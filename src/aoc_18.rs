@@ -0,0 +1,331 @@
+use {
+    crate::read_input::read_lines,
+    std::{
+        cmp::Reverse,
+        collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    },
+};
+
+pub fn aoc_18_01() -> usize {
+    Vault::parse(&read_lines(18)).shortest_collection()
+}
+
+pub fn aoc_18_02() -> usize {
+    Vault::parse(&read_lines(18))
+        .split_into_quadrants()
+        .shortest_collection()
+}
+
+type Coord = (usize, usize);
+type Keys = u32;
+
+// Keys are numbered 0..26 by letter, robot entrances come right after them
+const ENTRANCES: usize = 26;
+
+fn key_bit(key: char) -> Keys {
+    1 << (key as u8 - b'a')
+}
+
+fn door_bit(door: char) -> Keys {
+    1 << (door as u8 - b'A')
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Route {
+    target: usize,
+    steps: usize,
+    // Doors that must be open and keys lying on the way to the target
+    doors: Keys,
+    keys: Keys,
+}
+
+struct Vault {
+    cells: Vec<Vec<char>>,
+}
+
+impl Vault {
+    fn parse<S: AsRef<str>>(lines: &[S]) -> Self {
+        Self {
+            cells: lines
+                .iter()
+                .map(|line| line.as_ref().trim().chars().collect())
+                .filter(|row: &Vec<char>| !row.is_empty())
+                .collect(),
+        }
+    }
+
+    fn cell(&self, (x, y): &Coord) -> char {
+        self.cells[*y][*x]
+    }
+
+    fn entrances(&self) -> Vec<Coord> {
+        self.points(|c| c == '@')
+    }
+
+    fn points(&self, predicate: impl Fn(char) -> bool) -> Vec<Coord> {
+        let mut points = vec![];
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                if predicate(*c) {
+                    points.push((x, y));
+                }
+            }
+        }
+        points
+    }
+
+    // Replaces the single entrance and its surroundings with four walled-off entrances
+    fn split_into_quadrants(mut self) -> Self {
+        let entrances = self.entrances();
+        if entrances.len() != 1 {
+            panic!(
+                "Only a vault with one entrance can be split, this one has {}",
+                entrances.len()
+            );
+        }
+
+        let (x, y) = entrances[0];
+        let pattern = ["@#@", "###", "@#@"];
+        for (dy, row) in pattern.iter().enumerate() {
+            for (dx, c) in row.chars().enumerate() {
+                self.cells[y + dy - 1][x + dx - 1] = c;
+            }
+        }
+        self
+    }
+
+    fn all_keys(&self) -> Keys {
+        self.points(|c| c.is_ascii_lowercase())
+            .iter()
+            .fold(0, |keys, point| keys | key_bit(self.cell(point)))
+    }
+
+    // Breadth-first search from a point to every key reachable from it
+    fn routes_from(&self, start: &Coord) -> Vec<Route> {
+        let mut routes = vec![];
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(*start);
+        queue.push_back((*start, 0, 0, 0));
+
+        while let Some(((x, y), steps, doors, keys)) = queue.pop_front() {
+            for next in [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)].iter() {
+                let c = self.cell(next);
+                if c == '#' || !visited.insert(*next) {
+                    continue;
+                }
+
+                let (mut doors, mut keys) = (doors, keys);
+                if c.is_ascii_uppercase() {
+                    doors |= door_bit(c);
+                }
+                if c.is_ascii_lowercase() {
+                    routes.push(Route {
+                        target: (c as u8 - b'a') as usize,
+                        steps: steps + 1,
+                        doors,
+                        keys,
+                    });
+                    keys |= key_bit(c);
+                }
+                queue.push_back((*next, steps + 1, doors, keys));
+            }
+        }
+
+        routes
+    }
+
+    fn routes(&self) -> HashMap<usize, Vec<Route>> {
+        let mut routes = HashMap::new();
+        for (index, entrance) in self.entrances().iter().enumerate() {
+            routes.insert(ENTRANCES + index, self.routes_from(entrance));
+        }
+        for key in self.points(|c| c.is_ascii_lowercase()).iter() {
+            let node = (self.cell(key) as u8 - b'a') as usize;
+            routes.insert(node, self.routes_from(key));
+        }
+        routes
+    }
+
+    // Dijkstra over the robots' positions and the set of collected keys. Only
+    // one robot moves at a time, which is enough as the others can just wait.
+    fn shortest_collection(&self) -> usize {
+        let routes = self.routes();
+        let all_keys = self.all_keys();
+        let start: Vec<usize> = (0..self.entrances().len())
+            .map(|index| ENTRANCES + index)
+            .collect();
+
+        let mut best = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        best.insert((start.clone(), 0), 0);
+        queue.push(Reverse((0, start, 0)));
+
+        while let Some(Reverse((steps, positions, keys))) = queue.pop() {
+            if keys == all_keys {
+                return steps;
+            }
+            if best[&(positions.clone(), keys)] < steps {
+                continue;
+            }
+
+            for (robot, position) in positions.iter().enumerate() {
+                for route in routes[position].iter() {
+                    // Walking past a key without collecting it is never shorter
+                    // than stopping at that key first
+                    let collected = 1 << route.target;
+                    let passes_new_key = route.keys & !keys != 0;
+                    if keys & collected != 0 || route.doors & !keys != 0 || passes_new_key {
+                        continue;
+                    }
+
+                    let mut next = positions.clone();
+                    next[robot] = route.target;
+                    let next_keys = keys | collected;
+                    let next_steps = steps + route.steps;
+                    let state = (next.clone(), next_keys);
+                    if best.get(&state).is_none_or(|known| next_steps < *known) {
+                        best.insert(state, next_steps);
+                        queue.push(Reverse((next_steps, next, next_keys)));
+                    }
+                }
+            }
+        }
+
+        panic!("Not all keys can be collected")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shortest(text: &str) -> usize {
+        Vault::parse(&text.lines().collect::<Vec<_>>()).shortest_collection()
+    }
+
+    #[test]
+    fn collects_keys_with_one_robot() {
+        let examples = [
+            (
+                "\
+#########
+#b.A.@.a#
+#########",
+                8,
+            ),
+            (
+                "\
+########################
+#f.D.E.e.C.b.A.@.a.B.c.#
+######################.#
+#d.....................#
+########################",
+                86,
+            ),
+            (
+                "\
+########################
+#...............b.C.D.f#
+#.######################
+#.....@.a.B.c.d.A.e.F.g#
+########################",
+                132,
+            ),
+            (
+                "\
+#################
+#i.G..c...e..H.p#
+########.########
+#j.A..b...f..D.o#
+########@########
+#k.E..a...g..B.n#
+########.########
+#l.F..d...h..C.m#
+#################",
+                136,
+            ),
+            (
+                "\
+########################
+#@..............ac.GI.b#
+###d#e#f################
+###A#B#C################
+###g#h#i################
+########################",
+                81,
+            ),
+        ];
+
+        for (vault, expected) in examples.iter() {
+            assert_eq!(shortest(vault), *expected);
+        }
+    }
+
+    #[test]
+    fn splits_vault_into_quadrants() {
+        let vault = "\
+#######
+#a.#Cd#
+##...##
+##.@.##
+##...##
+#cB#Ab#
+#######";
+        let split = Vault::parse(&vault.lines().collect::<Vec<_>>()).split_into_quadrants();
+
+        assert_eq!(split.entrances(), vec![(2, 2), (4, 2), (2, 4), (4, 4)]);
+        assert_eq!(split.shortest_collection(), 8);
+    }
+
+    #[test]
+    fn collects_keys_with_four_robots() {
+        let examples = [
+            (
+                "\
+###############
+#d.ABC.#.....a#
+######@#@######
+###############
+######@#@######
+#b.....#.....c#
+###############",
+                24,
+            ),
+            (
+                "\
+#############
+#DcBa.#.GhKl#
+#.###@#@#I###
+#e#d#####j#k#
+###C#@#@###J#
+#fEbA.#.FgHi#
+#############",
+                32,
+            ),
+            (
+                "\
+#############
+#g#f.D#..h#l#
+#F###e#E###.#
+#dCba@#@BcIJ#
+#############
+#nK.L@#@G...#
+#M###N#H###.#
+#o#m..#i#jk.#
+#############",
+                72,
+            ),
+        ];
+
+        for (vault, expected) in examples.iter() {
+            assert_eq!(shortest(vault), *expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Only a vault with one entrance can be split, this one has 0")]
+    fn refuses_to_split_vault_without_entrance() {
+        Vault::parse(&["###", "#a#", "###"]).split_into_quadrants();
+    }
+}
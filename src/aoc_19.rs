@@ -0,0 +1,212 @@
+use crate::{opcode_computer::OpcodeComputer, read_input::read_intcode_program};
+
+const SCAN_SIZE: usize = 50;
+const SQUARE_SIZE: usize = 100;
+
+pub fn aoc_19_01() -> usize {
+    let mut drone = Drone::new(&read_intcode_program(19));
+
+    Scan::new(&mut drone, SCAN_SIZE, SCAN_SIZE).affected_points()
+}
+
+pub fn aoc_19_02() -> usize {
+    let mut drone = Drone::new(&read_intcode_program(19));
+    let start = Scan::new(&mut drone, SCAN_SIZE, SCAN_SIZE)
+        .lowest_left_edge()
+        .expect("Beam is not visible in the scanned area");
+    let (x, y) = fit_square(&mut drone, SQUARE_SIZE, start);
+
+    x * 10_000 + y
+}
+
+pub fn render_beam() -> String {
+    let mut drone = Drone::new(&read_intcode_program(19));
+
+    Scan::new(&mut drone, SCAN_SIZE, SCAN_SIZE).render()
+}
+
+type Coord = (usize, usize);
+
+trait Beam {
+    fn pulled(&mut self, coord: &Coord) -> bool;
+}
+
+// The drone program halts after each answer, so every query gets a fresh copy
+struct Drone {
+    program: Vec<isize>,
+}
+
+impl Drone {
+    fn new(program: &[isize]) -> Self {
+        Self {
+            program: program.to_vec(),
+        }
+    }
+}
+
+impl Beam for Drone {
+    fn pulled(&mut self, (x, y): &Coord) -> bool {
+        let mut computer = OpcodeComputer::new(&self.program);
+        computer.add_input(&(*x as isize));
+        computer.add_input(&(*y as isize));
+        computer.run();
+
+        match computer.get_output() {
+            Some(0) => false,
+            Some(1) => true,
+            other => panic!("Unexpected drone report {:?} for {}, {}", other, x, y),
+        }
+    }
+}
+
+struct Scan {
+    rows: Vec<Vec<bool>>,
+}
+
+impl Scan {
+    fn new(beam: &mut dyn Beam, width: usize, height: usize) -> Self {
+        Self {
+            rows: (0..height)
+                .map(|y| (0..width).map(|x| beam.pulled(&(x, y))).collect())
+                .collect(),
+        }
+    }
+
+    fn affected_points(&self) -> usize {
+        self.rows.iter().flatten().filter(|pulled| **pulled).count()
+    }
+
+    fn lowest_left_edge(&self) -> Option<Coord> {
+        self.rows
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(y, row)| row.iter().position(|pulled| *pulled).map(|x| (x, y)))
+    }
+
+    fn render(&self) -> String {
+        self.rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|pulled| if *pulled { '#' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// Follows the left edge of the beam down from a known beam point. The square whose
+// bottom left corner sits on the edge fits as soon as its top right corner is pulled.
+fn fit_square(beam: &mut dyn Beam, size: usize, start: Coord) -> Coord {
+    let (mut left, mut y) = start;
+
+    loop {
+        // Narrow rows close to the emitter may miss the beam entirely
+        if let Some(edge) = (left..=left + y).find(|x| beam.pulled(&(*x, y))) {
+            left = edge;
+            if y + 1 >= size && beam.pulled(&(left + size - 1, y + 1 - size)) {
+                return (left, y + 1 - size);
+            }
+        }
+        y += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct GridBeam {
+        rows: Vec<Vec<char>>,
+        queries: usize,
+    }
+
+    impl GridBeam {
+        fn parse(text: &str) -> Self {
+            Self {
+                rows: text.lines().map(|line| line.chars().collect()).collect(),
+                queries: 0,
+            }
+        }
+    }
+
+    impl Beam for GridBeam {
+        fn pulled(&mut self, (x, y): &Coord) -> bool {
+            self.queries += 1;
+            self.rows
+                .get(*y)
+                .and_then(|row| row.get(*x))
+                .is_some_and(|c| *c == '#')
+        }
+    }
+
+    // Pulls points between the lines y = 3x / 2 and y = 3x
+    struct ConeBeam {
+        queries: usize,
+    }
+
+    impl Beam for ConeBeam {
+        fn pulled(&mut self, (x, y): &Coord) -> bool {
+            self.queries += 1;
+            2 * y >= 3 * x && *y <= 3 * x
+        }
+    }
+
+    const EXAMPLE: &str = "\
+#.........
+.#........
+..##......
+...###....
+....###...
+.....####.
+......####
+......####
+.......###
+........##";
+
+    #[test]
+    fn counts_affected_points() {
+        let mut beam = GridBeam::parse(EXAMPLE);
+
+        assert_eq!(Scan::new(&mut beam, 10, 10).affected_points(), 27);
+        assert_eq!(beam.queries, 100);
+    }
+
+    #[test]
+    fn renders_scanned_beam() {
+        let mut beam = GridBeam::parse(EXAMPLE);
+
+        assert_eq!(Scan::new(&mut beam, 10, 10).render(), EXAMPLE);
+    }
+
+    #[test]
+    fn finds_lowest_left_edge() {
+        let mut beam = GridBeam::parse(EXAMPLE);
+        let scan = Scan::new(&mut beam, 10, 10);
+
+        assert_eq!(scan.lowest_left_edge(), Some((8, 9)));
+        assert_eq!(
+            Scan::new(&mut GridBeam::parse(".."), 2, 1).lowest_left_edge(),
+            None
+        );
+    }
+
+    #[test]
+    fn fits_square_closest_to_emitter() {
+        let mut beam = ConeBeam { queries: 0 };
+        let fitted = fit_square(&mut beam, 10, (1, 2));
+
+        let fits = |(x, y): Coord| {
+            let mut beam = ConeBeam { queries: 0 };
+            (0..10).all(|dy| (0..10).all(|dx| beam.pulled(&(x + dx, y + dy))))
+        };
+        let closest = (0..100)
+            .flat_map(|y| (0..100).map(move |x| (x, y)))
+            .find(|coord| fits(*coord))
+            .unwrap();
+        assert_eq!(fitted, closest);
+        assert!(beam.queries < 200);
+    }
+}
@@ -0,0 +1,299 @@
+use {
+    crate::read_input::read_lines,
+    std::{
+        cmp::Reverse,
+        collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    },
+};
+
+const ENTRANCE: &str = "AA";
+const EXIT: &str = "ZZ";
+
+pub fn aoc_20_01() -> usize {
+    Maze::parse(&read_lines(20))
+        .shortest_path(false)
+        .expect("Exit is not reachable")
+}
+
+pub fn aoc_20_02() -> usize {
+    Maze::parse(&read_lines(20))
+        .shortest_path(true)
+        .expect("Exit is not reachable through recursive levels")
+}
+
+type Coord = (usize, usize);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Side {
+    Outer,
+    Inner,
+}
+
+#[derive(Debug, PartialEq)]
+struct Portal {
+    label: String,
+    side: Side,
+    tile: Coord,
+}
+
+struct Maze {
+    portals: Vec<Portal>,
+    // Walking distances between the tiles in front of portals
+    paths: Vec<Vec<(usize, usize)>>,
+}
+
+impl Maze {
+    fn parse<S: AsRef<str>>(lines: &[S]) -> Self {
+        let cells: Vec<Vec<char>> = lines
+            .iter()
+            .map(|line| line.as_ref().chars().collect())
+            .collect();
+        let cell = |(x, y): Coord| {
+            cells
+                .get(y)
+                .and_then(|row| row.get(x))
+                .cloned()
+                .unwrap_or(' ')
+        };
+        let is_letter = |coord: Coord| cell(coord).is_ascii_uppercase();
+        let is_open = |coord: Coord| cell(coord) == '.';
+
+        let body: Vec<Coord> = (0..cells.len())
+            .flat_map(|y| (0..cells[y].len()).map(move |x| (x, y)))
+            .filter(|coord| cell(*coord) == '#' || cell(*coord) == '.')
+            .collect();
+        let min_x = body.iter().map(|(x, _)| *x).min().unwrap();
+        let max_x = body.iter().map(|(x, _)| *x).max().unwrap();
+        let min_y = body.iter().map(|(_, y)| *y).min().unwrap();
+        let max_y = body.iter().map(|(_, y)| *y).max().unwrap();
+
+        // Labels read left to right or top to bottom, the open tile is next to either letter
+        let mut portals = vec![];
+        for (y, row) in cells.iter().enumerate() {
+            for x in 0..row.len() {
+                if !is_letter((x, y)) {
+                    continue;
+                }
+
+                let (second, tiles) = if is_letter((x + 1, y)) {
+                    ((x + 1, y), [(x.wrapping_sub(1), y), (x + 2, y)])
+                } else if is_letter((x, y + 1)) {
+                    ((x, y + 1), [(x, y.wrapping_sub(1)), (x, y + 2)])
+                } else {
+                    continue;
+                };
+                let tile = *tiles
+                    .iter()
+                    .find(|tile| is_open(**tile))
+                    .unwrap_or_else(|| panic!("Portal at {}, {} leads nowhere", x, y));
+                let (tx, ty) = tile;
+                let side = if tx == min_x || tx == max_x || ty == min_y || ty == max_y {
+                    Side::Outer
+                } else {
+                    Side::Inner
+                };
+
+                portals.push(Portal {
+                    label: [cell((x, y)), cell(second)].iter().collect(),
+                    side,
+                    tile,
+                });
+            }
+        }
+
+        let tiles: HashMap<Coord, usize> = portals
+            .iter()
+            .enumerate()
+            .map(|(index, portal)| (portal.tile, index))
+            .collect();
+        let paths = portals
+            .iter()
+            .map(|portal| {
+                let mut paths = vec![];
+                let mut visited = HashSet::new();
+                let mut queue = VecDeque::new();
+                visited.insert(portal.tile);
+                queue.push_back((portal.tile, 0));
+
+                while let Some(((x, y), steps)) = queue.pop_front() {
+                    for next in [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)].iter() {
+                        if !is_open(*next) || !visited.insert(*next) {
+                            continue;
+                        }
+                        if let Some(index) = tiles.get(next) {
+                            paths.push((*index, steps + 1));
+                        }
+                        queue.push_back((*next, steps + 1));
+                    }
+                }
+
+                paths
+            })
+            .collect();
+
+        Self { portals, paths }
+    }
+
+    fn portal(&self, label: &str) -> usize {
+        self.portals
+            .iter()
+            .position(|portal| portal.label == label)
+            .unwrap_or_else(|| panic!("There's no {} portal", label))
+    }
+
+    fn partner(&self, index: usize) -> Option<usize> {
+        let label = &self.portals[index].label;
+        (0..self.portals.len())
+            .find(|other| *other != index && self.portals[*other].label == *label)
+    }
+
+    // Dijkstra over portal tiles and levels. In the recursive maze inner portals lead one
+    // level down and outer ones one level up, so the outer portals of level 0 are closed.
+    // Going deeper than there are portals never helps, which bounds the search.
+    fn shortest_path(&self, recursive: bool) -> Option<usize> {
+        let entrance = self.portal(ENTRANCE);
+        let exit = self.portal(EXIT);
+        let max_level = self.portals.len();
+
+        let mut best = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        best.insert((entrance, 0), 0);
+        queue.push(Reverse((0, entrance, 0)));
+
+        while let Some(Reverse((steps, index, level))) = queue.pop() {
+            if index == exit && level == 0 {
+                return Some(steps);
+            }
+            if best[&(index, level)] < steps {
+                continue;
+            }
+
+            let mut moves: Vec<(usize, usize, usize)> = self.paths[index]
+                .iter()
+                .map(|(next, distance)| (*next, level, steps + distance))
+                .collect();
+            if let Some(partner) = self.partner(index) {
+                let next_level = match (recursive, self.portals[index].side) {
+                    (false, _) => Some(0),
+                    (true, Side::Inner) if level < max_level => Some(level + 1),
+                    (true, Side::Outer) if level > 0 => Some(level - 1),
+                    _ => None,
+                };
+                if let Some(next_level) = next_level {
+                    moves.push((partner, next_level, steps + 1));
+                }
+            }
+
+            for (next, next_level, next_steps) in moves {
+                let state = (next, next_level);
+                if best.get(&state).is_none_or(|known| next_steps < *known) {
+                    best.insert(state, next_steps);
+                    queue.push(Reverse((next_steps, next, next_level)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Maze {
+        Maze::parse(&text.lines().collect::<Vec<_>>())
+    }
+
+    const EXAMPLE: &str = "         A
+         A
+  #######.#########
+  #######.........#
+  #######.#######.#
+  #######.#######.#
+  #######.#######.#
+  #####  B    ###.#
+BC...##  C    ###.#
+  ##.##       ###.#
+  ##...DE  F  ###.#
+  #####    G  ###.#
+  #########.#####.#
+DE..#######...###.#
+  #.#########.###.#
+FG..#########.....#
+  ###########.#####
+             Z
+             Z";
+
+    // AA only leads to the inner XX portal and ZZ only to the inner YY one, so the path
+    // has to go one level down and come back up through the outer portals
+    const DETOUR: &str = "       A   Z
+       A   Z
+  #####.###.###
+  ##....###..##
+  ##.#######.##
+  ##.       .##
+  ##.XX   YY.##
+  ###       ###
+XX.############
+  .......######
+  ######.######
+        Y
+        Y";
+
+    #[test]
+    fn pairs_inner_and_outer_portals() {
+        let maze = parse(EXAMPLE);
+        let portal = |label: &str, side: Side| {
+            maze.portals
+                .iter()
+                .find(|portal| portal.label == label && portal.side == side)
+                .map(|portal| portal.tile)
+        };
+
+        assert_eq!(maze.portals.len(), 8);
+        assert_eq!(portal("AA", Side::Outer), Some((9, 2)));
+        assert_eq!(portal("ZZ", Side::Outer), Some((13, 16)));
+        assert_eq!(portal("BC", Side::Inner), Some((9, 6)));
+        assert_eq!(portal("BC", Side::Outer), Some((2, 8)));
+        assert_eq!(portal("DE", Side::Inner), Some((6, 10)));
+        assert_eq!(portal("DE", Side::Outer), Some((2, 13)));
+        assert_eq!(portal("FG", Side::Inner), Some((11, 12)));
+        assert_eq!(portal("FG", Side::Outer), Some((2, 15)));
+    }
+
+    #[test]
+    fn finds_shortest_path_through_portals() {
+        assert_eq!(parse(EXAMPLE).shortest_path(false), Some(23));
+        assert_eq!(parse(DETOUR).shortest_path(false), Some(22));
+    }
+
+    #[test]
+    fn finds_shortest_path_through_levels() {
+        assert_eq!(parse(EXAMPLE).shortest_path(true), Some(26));
+        assert_eq!(parse(DETOUR).shortest_path(true), Some(22));
+    }
+
+    #[test]
+    fn keeps_outer_portals_of_top_level_closed() {
+        // The same walls, but from AA the only way out is the outer XX portal
+        let maze = parse(
+            "       Y   Z
+       Y   Z
+  #####.###.###
+  ##....###..##
+  ##.#######.##
+  ##.       .##
+  ##.XX   YY.##
+  ###       ###
+AA.############
+  .......######
+  ######.######
+        X
+        X",
+        );
+
+        assert_eq!(maze.shortest_path(false), Some(22));
+        assert_eq!(maze.shortest_path(true), None);
+    }
+}
@@ -0,0 +1,668 @@
+use {
+    crate::{ascii_computer::AsciiComputer, read_input::read_intcode_program},
+    std::{collections::HashSet, fmt, str::FromStr},
+};
+
+const MAX_INSTRUCTIONS: usize = 15;
+const JUMP_LENGTH: usize = 4;
+
+pub fn aoc_21_01() -> isize {
+    survey(&read_intcode_program(21), Mode::Walk)
+}
+
+pub fn aoc_21_02() -> isize {
+    survey(&read_intcode_program(21), Mode::Run)
+}
+
+// Searches for a script that survives every hull seen so far, and whenever the droid
+// falls anyway, learns the hull from the death animation and searches again
+fn survey(program: &[isize], mode: Mode) -> isize {
+    let mut hulls: Vec<Hull> = vec![];
+    let mut candidates: Vec<Script> = vec![];
+
+    loop {
+        // Scripts of the last search that also pass the new hull are still the shortest
+        candidates.retain(|script| hulls.iter().all(|hull| hull.simulate(script, mode).is_ok()));
+        if candidates.is_empty() {
+            candidates = search(&hulls, mode, MAX_INSTRUCTIONS);
+        }
+        let script = candidates
+            .first()
+            .unwrap_or_else(|| panic!("No script survives all {} hulls", hulls.len()));
+        match run_script(program, script, mode) {
+            Outcome::HullDamage(damage) => return damage,
+            Outcome::Fell(death) => {
+                if hulls.contains(&death.hull) {
+                    panic!(
+                        "Droid fell at {} although the script passed there",
+                        death.hull
+                    );
+                }
+                hulls.push(death.hull);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Walk,
+    Run,
+}
+
+impl Mode {
+    fn sensors(self) -> usize {
+        match self {
+            Mode::Walk => 4,
+            Mode::Run => 9,
+        }
+    }
+
+    fn command(self) -> &'static str {
+        match self {
+            Mode::Walk => "WALK",
+            Mode::Run => "RUN",
+        }
+    }
+}
+
+// Sensors A to I look one to nine tiles ahead, T and J are the writable registers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Register {
+    Sensor(usize),
+    Temporary,
+    Jump,
+}
+
+impl Register {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'A'..='I' => Some(Register::Sensor(c as usize - 'A' as usize)),
+            'T' => Some(Register::Temporary),
+            'J' => Some(Register::Jump),
+            _ => None,
+        }
+    }
+
+    fn writable(self) -> bool {
+        self == Register::Temporary || self == Register::Jump
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Register::Sensor(index) => write!(f, "{}", (b'A' + *index as u8) as char),
+            Register::Temporary => write!(f, "T"),
+            Register::Jump => write!(f, "J"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+    And,
+    Or,
+    Not,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Operation::And => "AND",
+            Operation::Or => "OR",
+            Operation::Not => "NOT",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Instruction {
+    pub operation: Operation,
+    pub source: Register,
+    pub target: Register,
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.operation, self.source, self.target)
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let register = |name: &str| {
+            let mut chars = name.chars();
+            match (chars.next().and_then(Register::from_char), chars.next()) {
+                (Some(register), None) => Ok(register),
+                _ => Err(format!("Unknown register {}", name)),
+            }
+        };
+
+        match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [operation, source, target] => {
+                let operation = match *operation {
+                    "AND" => Operation::And,
+                    "OR" => Operation::Or,
+                    "NOT" => Operation::Not,
+                    _ => return Err(format!("Unknown operation {}", operation)),
+                };
+                let target = register(target)?;
+                if !target.writable() {
+                    return Err(format!("Register {} is read-only", target));
+                }
+
+                Ok(Instruction {
+                    operation,
+                    source: register(source)?,
+                    target,
+                })
+            }
+            _ => Err(format!("Malformed instruction {}", line)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Script {
+    pub instructions: Vec<Instruction>,
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let instructions = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { instructions })
+    }
+
+    pub fn validate(&self, mode: Mode) -> Result<(), String> {
+        if self.instructions.len() > MAX_INSTRUCTIONS {
+            return Err(format!(
+                "Script has {} instructions, the droid only fits {}",
+                self.instructions.len(),
+                MAX_INSTRUCTIONS
+            ));
+        }
+        for instruction in self.instructions.iter() {
+            if let Register::Sensor(index) = instruction.source {
+                if index >= mode.sensors() {
+                    return Err(format!(
+                        "Sensor {} is not available in {} mode",
+                        instruction.source,
+                        mode.command()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // The lines the droid expects, finished with the command that starts it
+    pub fn lines(&self, mode: Mode) -> Vec<String> {
+        self.instructions
+            .iter()
+            .map(Instruction::to_string)
+            .chain(std::iter::once(mode.command().to_string()))
+            .collect()
+    }
+
+    // Sensor bit n is set when there's ground n + 1 tiles ahead
+    fn jumps(&self, sensors: u16) -> bool {
+        let registers = self.instructions.iter().fold(0, |registers, instruction| {
+            step(registers, instruction, sensors)
+        });
+
+        registers & 2 != 0
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instruction in self.instructions.iter() {
+            writeln!(f, "{}", instruction)?;
+        }
+        Ok(())
+    }
+}
+
+// Everything past the known part of the hull is assumed to be solid ground
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hull {
+    ground: Vec<bool>,
+}
+
+impl Hull {
+    pub fn parse(text: &str) -> Self {
+        Self {
+            ground: text.trim().chars().map(|c| c != '.').collect(),
+        }
+    }
+
+    fn is_ground(&self, position: usize) -> bool {
+        self.ground.get(position).cloned().unwrap_or(true)
+    }
+
+    fn sensors(&self, position: usize, mode: Mode) -> u16 {
+        (0..mode.sensors())
+            .filter(|index| self.is_ground(position + 1 + index))
+            .fold(0, |sensors, index| sensors | 1 << index)
+    }
+
+    // Walks the droid across the hull and returns where it fell, if it did
+    pub fn simulate(&self, script: &Script, mode: Mode) -> Result<(), usize> {
+        self.walk(|position| script.jumps(self.sensors(position, mode)))
+    }
+
+    fn walk(&self, jumps: impl Fn(usize) -> bool) -> Result<(), usize> {
+        let mut position = 0;
+
+        while position < self.ground.len() {
+            position += if jumps(position) { JUMP_LENGTH } else { 1 };
+            if !self.is_ground(position) {
+                return Err(position);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Hull {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text: String = self
+            .ground
+            .iter()
+            .map(|ground| if *ground { '#' } else { '.' })
+            .collect();
+        write!(f, "{}", text)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Death {
+    pub hull: Hull,
+    pub position: usize,
+}
+
+impl Death {
+    // The animation is a series of frames separated by empty lines. The hull is the
+    // bottom row of each frame and in the last one the droid is already down in a hole.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (_, animation) = text.split_once("Didn't make it across:")?;
+        let frames: Vec<Vec<&str>> = animation
+            .split("\n\n")
+            .map(|frame| frame.lines().filter(|line| !line.is_empty()).collect())
+            .filter(|frame: &Vec<&str>| !frame.is_empty())
+            .collect();
+
+        let hull = Hull::parse(frames.first()?.last()?);
+        let position = frames.last()?.last()?.find('@')?;
+        Some(Self { hull, position })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    HullDamage(isize),
+    Fell(Death),
+}
+
+pub fn run_script(program: &[isize], script: &Script, mode: Mode) -> Outcome {
+    if let Err(message) = script.validate(mode) {
+        panic!("{}", message);
+    }
+
+    let mut computer = AsciiComputer::new(&program.to_vec());
+    for line in script.lines(mode) {
+        computer.send_line(&line);
+    }
+    computer.run();
+
+    let output = computer.read();
+    match output.values.last() {
+        Some(damage) => Outcome::HullDamage(*damage),
+        None => Outcome::Fell(
+            Death::parse(&output.text)
+                .unwrap_or_else(|| panic!("Unexpected droid report:\n{}", output.text)),
+        ),
+    }
+}
+
+// Searches scripts that fold one sensor at a time into J, negating it through T when
+// needed, shortest first. T never outlives a fold, so scripts that jump on the same
+// readings seen on the hulls behave the same from then on and only one is extended.
+// Returns every surviving script of the shortest length, so the ones that also pass
+// the next hull can be tried without searching again.
+pub fn search(hulls: &[Hull], mode: Mode, max_length: usize) -> Vec<Script> {
+    let seen = Bits::from_fn(|reading| {
+        hulls.iter().any(|hull| {
+            (0..hull.ground.len())
+                .any(|position| hull.is_ground(position) && hull.sensors(position, mode) == reading)
+        })
+    });
+    let sensors: Vec<Bits> = (0..mode.sensors())
+        .map(|sensor| Bits::from_fn(|reading| reading & 1 << sensor != 0))
+        .collect();
+    let survives = |jumps: &Bits| {
+        hulls.iter().all(|hull| {
+            hull.walk(|position| jumps.get(hull.sensors(position, mode)))
+                .is_ok()
+        })
+    };
+
+    let instruction = |operation, source, target| Instruction {
+        operation,
+        source,
+        target,
+    };
+    // J starts out false, so the first fold sets it to a sensor or its negation
+    // and every later one combines J with another sensor. Folds are ranked so that
+    // runs of the same operation, which can be reordered freely, are tried only once.
+    let mut first_folds = vec![];
+    let mut folds = vec![];
+    for (index, sensor) in (0..mode.sensors()).map(Register::Sensor).enumerate() {
+        first_folds.push(Fold {
+            operation: Operation::Or,
+            rank: 2 * index,
+            instructions: vec![instruction(Operation::Or, sensor, Register::Jump)],
+        });
+        first_folds.push(Fold {
+            operation: Operation::Or,
+            rank: 2 * index + 1,
+            instructions: vec![instruction(Operation::Not, sensor, Register::Jump)],
+        });
+        for operation in [Operation::And, Operation::Or].iter() {
+            folds.push(Fold {
+                operation: *operation,
+                rank: 2 * index,
+                instructions: vec![instruction(*operation, sensor, Register::Jump)],
+            });
+            folds.push(Fold {
+                operation: *operation,
+                rank: 2 * index + 1,
+                instructions: vec![
+                    instruction(Operation::Not, sensor, Register::Temporary),
+                    instruction(*operation, Register::Temporary, Register::Jump),
+                ],
+            });
+        }
+    }
+
+    // Each script is the fold that extends an earlier one. Scripts of a length are only
+    // built once all shorter ones are known, so the first script to jump on some readings
+    // is a shortest one.
+    let mut scripts: Vec<(usize, Option<&Fold>)> = vec![(0, None)];
+    let mut known = HashSet::new();
+    let mut by_length = vec![vec![(0, Bits::default())]];
+    known.insert(Bits::default());
+
+    for length in 0..=max_length {
+        if length > 0 {
+            let mut level = vec![];
+            // Folds are one or two instructions long
+            for (shorter, states) in by_length.iter().enumerate().skip(length.saturating_sub(2)) {
+                for (id, jumps) in states {
+                    let last = scripts[*id].1;
+                    for fold in if *id == 0 { &first_folds } else { &folds }.iter() {
+                        let reordered = last.is_some_and(|last| {
+                            last.operation == fold.operation && last.rank >= fold.rank
+                        });
+                        if shorter + fold.instructions.len() != length || reordered {
+                            continue;
+                        }
+
+                        let next_jumps = jumps.fold(&fold.instructions, &sensors, &seen);
+                        if known.insert(next_jumps) {
+                            scripts.push((*id, Some(fold)));
+                            level.push((scripts.len() - 1, next_jumps));
+                        }
+                    }
+                }
+            }
+            by_length.push(level);
+        }
+
+        let survivors: Vec<Script> = by_length[length]
+            .iter()
+            .filter(|(_, jumps)| survives(jumps))
+            .map(|(id, _)| {
+                let mut parts = vec![];
+                let mut id = *id;
+                while let (parent, Some(fold)) = scripts[id] {
+                    parts.push(fold);
+                    id = parent;
+                }
+                let instructions = parts
+                    .iter()
+                    .rev()
+                    .flat_map(|fold| fold.instructions.iter().cloned())
+                    .collect();
+                Script { instructions }
+            })
+            .collect();
+        if !survivors.is_empty() {
+            return survivors;
+        }
+    }
+
+    vec![]
+}
+
+struct Fold {
+    operation: Operation,
+    rank: usize,
+    instructions: Vec<Instruction>,
+}
+
+// A register's value for each of the 512 possible sensor readings
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+struct Bits([u64; 8]);
+
+impl Bits {
+    fn from_fn(bit: impl Fn(u16) -> bool) -> Self {
+        let mut bits = Self::default();
+        for reading in (0..512).filter(|reading| bit(*reading)) {
+            bits.0[reading as usize / 64] |= 1 << (reading % 64);
+        }
+        bits
+    }
+
+    fn get(&self, reading: u16) -> bool {
+        self.0[reading as usize / 64] & 1 << (reading % 64) != 0
+    }
+
+    // Runs the instructions on J with a fresh T, 64 readings at a time. Readings that
+    // never occurred are cleared, so registers only differ where it matters.
+    fn fold(&self, instructions: &[Instruction], sensors: &[Bits], seen: &Bits) -> Self {
+        let mut bits = *self;
+        for (index, word) in bits.0.iter_mut().enumerate() {
+            let (mut temporary, mut jump) = (0, *word);
+            for instruction in instructions {
+                let source = match instruction.source {
+                    Register::Sensor(sensor) => sensors[sensor].0[index],
+                    Register::Temporary => temporary,
+                    Register::Jump => jump,
+                };
+                let target = if instruction.target == Register::Temporary {
+                    &mut temporary
+                } else {
+                    &mut jump
+                };
+                *target = match instruction.operation {
+                    Operation::And => *target & source,
+                    Operation::Or => *target | source,
+                    Operation::Not => !source,
+                };
+            }
+            *word = jump & seen.0[index];
+        }
+        bits
+    }
+}
+
+// Registers are packed as T in bit 0 and J in bit 1
+fn step(registers: u8, instruction: &Instruction, reading: u16) -> u8 {
+    let temporary = registers & 1 != 0;
+    let jump = registers & 2 != 0;
+    let source = match instruction.source {
+        Register::Sensor(index) => reading & (1 << index) != 0,
+        Register::Temporary => temporary,
+        Register::Jump => jump,
+    };
+    let target = if instruction.target == Register::Temporary {
+        temporary
+    } else {
+        jump
+    };
+    let value = match instruction.operation {
+        Operation::And => target && source,
+        Operation::Or => target || source,
+        Operation::Not => !source,
+    };
+
+    if instruction.target == Register::Temporary {
+        (registers & !1) | value as u8
+    } else {
+        (registers & !2) | (value as u8) << 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANIMATION: &str = "Input instructions:
+
+Walking...
+
+
+Didn't make it across:
+
+.................
+.................
+@................
+#####.###########
+
+.................
+.................
+.@...............
+#####.###########
+
+.................
+..@..............
+.................
+#####.###########
+
+...@.............
+.................
+.................
+#####.###########
+
+.................
+....@............
+.................
+#####.###########
+
+.................
+.................
+.....@...........
+#####.###########
+
+.................
+.................
+.................
+#####@###########
+
+";
+
+    fn script(text: &str) -> Script {
+        Script::parse(text).unwrap()
+    }
+
+    #[test]
+    fn serialises_script_for_droid() {
+        let script = script("NOT A J\nOR T J\nAND I T");
+
+        assert_eq!(
+            script.lines(Mode::Run),
+            vec!["NOT A J", "OR T J", "AND I T", "RUN"]
+        );
+        assert_eq!(script.to_string(), "NOT A J\nOR T J\nAND I T\n");
+    }
+
+    #[test]
+    fn rejects_invalid_scripts() {
+        assert_eq!(
+            Script::parse("NOT A B"),
+            Err("Register B is read-only".to_string())
+        );
+        assert_eq!(
+            Script::parse("XOR A J"),
+            Err("Unknown operation XOR".to_string())
+        );
+        assert_eq!(
+            script("NOT E J").validate(Mode::Walk),
+            Err("Sensor E is not available in WALK mode".to_string())
+        );
+        assert_eq!(script("NOT E J").validate(Mode::Run), Ok(()));
+        assert!(script(&"NOT A J\n".repeat(16))
+            .validate(Mode::Walk)
+            .is_err());
+    }
+
+    #[test]
+    fn parses_death_animation() {
+        let death = Death::parse(ANIMATION).unwrap();
+
+        assert_eq!(death.hull, Hull::parse("#####.###########"));
+        assert_eq!(death.position, 5);
+        assert_eq!(Death::parse("Walking...\n"), None);
+    }
+
+    #[test]
+    fn simulates_droid_on_hull() {
+        let hull = Hull::parse("#####.###########");
+
+        assert_eq!(hull.simulate(&script("NOT D J"), Mode::Walk), Err(5));
+        assert_eq!(hull.simulate(&Script::default(), Mode::Walk), Err(5));
+        assert_eq!(hull.simulate(&script("NOT A J"), Mode::Walk), Ok(()));
+    }
+
+    #[test]
+    fn searches_script_for_walking() {
+        let hulls: Vec<Hull> = [
+            "#####.###########",
+            "#####..#.########",
+            "#####...#########",
+        ]
+        .iter()
+        .map(|hull| Hull::parse(hull))
+        .collect();
+        let scripts = search(&hulls, Mode::Walk, 6);
+
+        assert!(scripts.len() > 1);
+        for script in scripts.iter() {
+            assert!(hulls
+                .iter()
+                .all(|hull| hull.simulate(script, Mode::Walk).is_ok()));
+            assert!(script.validate(Mode::Walk).is_ok());
+            assert_eq!(script.instructions.len(), scripts[0].instructions.len());
+        }
+        assert_eq!(search(&[], Mode::Walk, 6), vec![Script::default()]);
+    }
+
+    #[test]
+    fn gives_up_on_impossible_hull() {
+        let hulls = [Hull::parse("#####....########")];
+
+        assert_eq!(search(&hulls, Mode::Run, 4), vec![]);
+    }
+}
@@ -0,0 +1,215 @@
+use {crate::read_input::read_lines, num_integer::Integer};
+
+const SMALL_DECK: i128 = 10_007;
+const CARD: i128 = 2019;
+const HUGE_DECK: i128 = 119_315_717_514_047;
+const SHUFFLES: u64 = 101_741_582_076_661;
+const POSITION: i128 = 2020;
+
+pub fn aoc_22_01() -> i128 {
+    shuffle(&parse_techniques(&read_lines(22)), SMALL_DECK).apply(CARD)
+}
+
+pub fn aoc_22_02() -> i128 {
+    shuffle(&parse_techniques(&read_lines(22)), HUGE_DECK)
+        .power(SHUFFLES)
+        .inverse()
+        .apply(POSITION)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Technique {
+    NewStack,
+    Cut(i128),
+    Increment(i128),
+}
+
+impl Technique {
+    fn parse(line: &str) -> Self {
+        let number = |prefix: &str| {
+            line[prefix.len()..]
+                .parse()
+                .unwrap_or_else(|_| panic!("Wrong number in {}", line))
+        };
+
+        if line == "deal into new stack" {
+            Technique::NewStack
+        } else if line.starts_with("cut ") {
+            Technique::Cut(number("cut "))
+        } else if line.starts_with("deal with increment ") {
+            Technique::Increment(number("deal with increment "))
+        } else {
+            panic!("Unknown technique {}", line)
+        }
+    }
+
+    // Where the card at position x ends up
+    fn to_map(self, deck: i128) -> LinearMap {
+        let (a, b) = match self {
+            Technique::NewStack => (-1, -1),
+            Technique::Cut(n) => (1, -n),
+            Technique::Increment(n) => (n, 0),
+        };
+
+        LinearMap::new(a, b, deck)
+    }
+}
+
+fn parse_techniques<S: AsRef<str>>(lines: &[S]) -> Vec<Technique> {
+    lines
+        .iter()
+        .map(|line| line.as_ref().trim())
+        .filter(|line| !line.is_empty())
+        .map(Technique::parse)
+        .collect()
+}
+
+fn shuffle(techniques: &[Technique], deck: i128) -> LinearMap {
+    techniques
+        .iter()
+        .fold(LinearMap::new(1, 0, deck), |map, technique| {
+            map.then(&technique.to_map(deck))
+        })
+}
+
+// x -> a * x + b (mod m). Products of two residues of a deck with fewer than 2^63
+// cards still fit into 128 bits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct LinearMap {
+    a: i128,
+    b: i128,
+    m: i128,
+}
+
+impl LinearMap {
+    fn new(a: i128, b: i128, m: i128) -> Self {
+        Self {
+            a: a.rem_euclid(m),
+            b: b.rem_euclid(m),
+            m,
+        }
+    }
+
+    fn apply(&self, x: i128) -> i128 {
+        (self.a * x + self.b).rem_euclid(self.m)
+    }
+
+    // Applies self first and other second
+    fn then(&self, other: &Self) -> Self {
+        Self::new(
+            self.a * other.a % self.m,
+            (self.b * other.a + other.b) % self.m,
+            self.m,
+        )
+    }
+
+    // Exponentiation by squaring
+    fn power(&self, mut times: u64) -> Self {
+        let mut result = Self::new(1, 0, self.m);
+        let mut square = *self;
+
+        while times > 0 {
+            if times & 1 == 1 {
+                result = result.then(&square);
+            }
+            square = square.then(&square);
+            times >>= 1;
+        }
+
+        result
+    }
+
+    fn inverse(&self) -> Self {
+        let gcd = self.a.extended_gcd(&self.m);
+        if gcd.gcd != 1 {
+            panic!(
+                "{} has no inverse modulo {}, the shuffle loses cards",
+                self.a, self.m
+            );
+        }
+
+        Self::new(gcd.x, -gcd.x * self.b % self.m, self.m)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Card at each position of a deck of ten, after the shuffle
+    fn deal(text: &str) -> Vec<i128> {
+        let map = shuffle(&parse_techniques(&text.lines().collect::<Vec<_>>()), 10);
+        let inverse = map.inverse();
+
+        (0..10).map(|position| inverse.apply(position)).collect()
+    }
+
+    #[test]
+    fn applies_single_techniques() {
+        assert_eq!(
+            deal("deal into new stack"),
+            vec![9, 8, 7, 6, 5, 4, 3, 2, 1, 0]
+        );
+        assert_eq!(deal("cut 3"), vec![3, 4, 5, 6, 7, 8, 9, 0, 1, 2]);
+        assert_eq!(deal("cut -4"), vec![6, 7, 8, 9, 0, 1, 2, 3, 4, 5]);
+        assert_eq!(
+            deal("deal with increment 3"),
+            vec![0, 7, 4, 1, 8, 5, 2, 9, 6, 3]
+        );
+    }
+
+    #[test]
+    fn composes_published_shuffles() {
+        let examples = [
+            (
+                "deal with increment 7\ndeal into new stack\ndeal into new stack",
+                vec![0, 3, 6, 9, 2, 5, 8, 1, 4, 7],
+            ),
+            (
+                "cut 6\ndeal with increment 7\ndeal into new stack",
+                vec![3, 0, 7, 4, 1, 8, 5, 2, 9, 6],
+            ),
+            (
+                "deal with increment 7\ndeal with increment 9\ncut -2",
+                vec![6, 3, 0, 7, 4, 1, 8, 5, 2, 9],
+            ),
+            (
+                "deal into new stack\ncut -2\ndeal with increment 7\ncut 8\ncut -4
+deal with increment 7\ncut 3\ndeal with increment 9\ndeal with increment 3\ncut -1",
+                vec![9, 2, 5, 8, 1, 4, 7, 0, 3, 6],
+            ),
+        ];
+
+        for (shuffle, expected) in examples.iter() {
+            assert_eq!(deal(shuffle), *expected);
+        }
+    }
+
+    #[test]
+    fn repeats_shuffle_by_squaring() {
+        let map = shuffle(
+            &parse_techniques(&["deal with increment 7", "cut -3", "deal into new stack"]),
+            SMALL_DECK,
+        );
+        let repeated = (0..13).fold(LinearMap::new(1, 0, SMALL_DECK), |result, _| {
+            result.then(&map)
+        });
+
+        assert_eq!(map.power(13), repeated);
+        assert_eq!(map.power(0).apply(CARD), CARD);
+    }
+
+    #[test]
+    fn inverts_shuffle_of_huge_deck() {
+        let map = LinearMap::new(HUGE_DECK - 3, 98_765_432_123, HUGE_DECK).power(SHUFFLES);
+
+        assert_eq!(map.inverse().apply(map.apply(POSITION)), POSITION);
+        assert_eq!(map.then(&map.inverse()), LinearMap::new(1, 0, HUGE_DECK));
+    }
+
+    #[test]
+    #[should_panic(expected = "2 has no inverse modulo 10, the shuffle loses cards")]
+    fn refuses_to_invert_lossy_deal() {
+        shuffle(&parse_techniques(&["deal with increment 2"]), 10).inverse();
+    }
+}
@@ -0,0 +1,190 @@
+use {crate::read_input::read_lines, std::collections::HashSet};
+
+const SIZE: usize = 5;
+const CENTRE: usize = 12;
+const MINUTES: usize = 200;
+
+pub fn aoc_24_01() -> u32 {
+    first_repeated(parse_layout(&read_lines(24)))
+}
+
+pub fn aoc_24_02() -> u32 {
+    recursive_bugs(parse_layout(&read_lines(24)), MINUTES)
+}
+
+// Tile (x, y) is bit 5 * y + x, so the layout itself is its biodiversity rating
+type Layout = u32;
+
+fn parse_layout<S: AsRef<str>>(lines: &[S]) -> Layout {
+    lines
+        .iter()
+        .map(|line| line.as_ref().trim())
+        .filter(|line| !line.is_empty())
+        .flat_map(|line| line.chars())
+        .enumerate()
+        .filter(|(_, c)| *c == '#')
+        .fold(0, |layout, (index, _)| layout | 1 << index)
+}
+
+fn has_bug(layout: Layout, x: usize, y: usize) -> bool {
+    layout & 1 << (y * SIZE + x) != 0
+}
+
+// A bug survives next to exactly one bug, an empty tile gets infested next to one or two
+fn lives(bug: bool, neighbours: u32) -> bool {
+    neighbours == 1 || (!bug && neighbours == 2)
+}
+
+fn step(layout: Layout) -> Layout {
+    let mut next = 0;
+
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let neighbours = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+                .iter()
+                .map(|(dx, dy)| (x as isize + dx, y as isize + dy))
+                .filter(|(nx, ny)| {
+                    (0..SIZE as isize).contains(nx) && (0..SIZE as isize).contains(ny)
+                })
+                .filter(|(nx, ny)| has_bug(layout, *nx as usize, *ny as usize))
+                .count() as u32;
+            if lives(has_bug(layout, x, y), neighbours) {
+                next |= 1 << (y * SIZE + x);
+            }
+        }
+    }
+
+    next
+}
+
+fn first_repeated(layout: Layout) -> Layout {
+    let mut seen = HashSet::new();
+    let mut layout = layout;
+
+    while seen.insert(layout) {
+        layout = step(layout);
+    }
+
+    layout
+}
+
+// Bugs next to tile (x, y) of the grid at depth `levels[index]`. Levels are ordered from
+// the outermost one, each filling the centre tile of the one before it.
+fn recursive_neighbours(levels: &[Layout], index: usize, x: usize, y: usize) -> u32 {
+    let outer = index.checked_sub(1).map_or(0, |outer| levels[outer]);
+    let inner = levels.get(index + 1).cloned().unwrap_or(0);
+    let mut count = 0;
+
+    for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)].iter() {
+        let (nx, ny) = (x as isize + dx, y as isize + dy);
+        if !(0..SIZE as isize).contains(&nx) || !(0..SIZE as isize).contains(&ny) {
+            // Leaving the grid leads to the tile next to the centre of the outer level
+            let (ox, oy) = ((2 + dx) as usize, (2 + dy) as usize);
+            count += has_bug(outer, ox, oy) as u32;
+        } else if (nx as usize, ny as usize) == (2, 2) {
+            // Entering the centre leads to the whole facing edge of the inner level
+            count += (0..SIZE)
+                .map(|i| match (dx, dy) {
+                    (0, 1) => (i, 0),
+                    (0, -1) => (i, SIZE - 1),
+                    (1, 0) => (0, i),
+                    _ => (SIZE - 1, i),
+                })
+                .filter(|(ix, iy)| has_bug(inner, *ix, *iy))
+                .count() as u32;
+        } else {
+            count += has_bug(levels[index], nx as usize, ny as usize) as u32;
+        }
+    }
+
+    count
+}
+
+fn recursive_step(levels: &[Layout]) -> Vec<Layout> {
+    // Bugs spread at most one level per minute in either direction
+    let mut padded = vec![0];
+    padded.extend_from_slice(levels);
+    padded.push(0);
+
+    let mut next: Vec<Layout> = (0..padded.len())
+        .map(|index| {
+            let mut layout = 0;
+            for tile in (0..SIZE * SIZE).filter(|tile| *tile != CENTRE) {
+                let (x, y) = (tile % SIZE, tile / SIZE);
+                let neighbours = recursive_neighbours(&padded, index, x, y);
+                if lives(has_bug(padded[index], x, y), neighbours) {
+                    layout |= 1 << tile;
+                }
+            }
+            layout
+        })
+        .collect();
+
+    while next.len() > 1 && next[0] == 0 {
+        next.remove(0);
+    }
+    while next.len() > 1 && next[next.len() - 1] == 0 {
+        next.pop();
+    }
+    next
+}
+
+fn recursive_bugs(layout: Layout, minutes: usize) -> u32 {
+    let levels = (0..minutes).fold(vec![layout & !(1 << CENTRE)], |levels, _| {
+        recursive_step(&levels)
+    });
+
+    levels.iter().map(|layout| layout.count_ones()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(text: &str) -> Layout {
+        parse_layout(&text.lines().collect::<Vec<_>>())
+    }
+
+    const EXAMPLE: &str = "\
+....#
+#..#.
+#..##
+..#..
+#....";
+
+    #[test]
+    fn simulates_bugs_minute_by_minute() {
+        let after_one = layout("#..#.\n####.\n###.#\n##.##\n.##..");
+        let after_four = layout("####.\n....#\n##..#\n.....\n##...");
+
+        assert_eq!(step(layout(EXAMPLE)), after_one);
+        assert_eq!(
+            (0..4).fold(layout(EXAMPLE), |layout, _| step(layout)),
+            after_four
+        );
+    }
+
+    #[test]
+    fn rates_first_repeated_layout() {
+        let repeated = first_repeated(layout(EXAMPLE));
+
+        assert_eq!(repeated, layout(".....\n.....\n.....\n#....\n.#..."));
+        assert_eq!(repeated, 2_129_920);
+    }
+
+    #[test]
+    fn counts_bugs_in_recursive_grids() {
+        assert_eq!(recursive_bugs(layout(EXAMPLE), 10), 99);
+    }
+
+    #[test]
+    fn counts_neighbours_across_levels() {
+        // Outer level: bug above the centre. Inner level: the whole top row.
+        let levels = [1 << 7, 0, 0b11111];
+
+        assert_eq!(recursive_neighbours(&levels, 1, 2, 0), 1);
+        assert_eq!(recursive_neighbours(&levels, 1, 2, 1), 5);
+        assert_eq!(recursive_neighbours(&levels, 1, 0, 0), 1);
+        assert_eq!(recursive_neighbours(&levels, 1, 1, 1), 0);
+    }
+}
@@ -0,0 +1,500 @@
+use {
+    crate::{ascii_computer::AsciiComputer, read_input::try_read_intcode_program},
+    std::{
+        collections::{HashMap, VecDeque},
+        io::{self, BufRead, Write},
+    },
+};
+
+const CHECKPOINT: &str = "Security Checkpoint";
+// Taking any of these ends the game or gets the droid stuck
+const DEADLY_ITEMS: [&str; 5] = [
+    "escape pod",
+    "giant electromagnet",
+    "infinite loop",
+    "molten lava",
+    "photons",
+];
+
+// The day 25 input is not part of the repository, so both report a missing one
+pub fn aoc_25_01() -> io::Result<String> {
+    Ok(solve(&mut Droid::new(&try_read_intcode_program(25)?)))
+}
+
+pub fn play() -> io::Result<()> {
+    let mut droid = Droid::new(&try_read_intcode_program(25)?);
+    print!("{}", droid.start());
+
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        print!("{}", droid.send(&line?));
+        io::stdout().flush()?;
+        if droid.computer.halted() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+trait Ship {
+    // Everything printed up to the first prompt
+    fn start(&mut self) -> String;
+    // Everything printed in response to the command, up to the next prompt
+    fn send(&mut self, command: &str) -> String;
+}
+
+struct Droid {
+    computer: AsciiComputer,
+}
+
+impl Droid {
+    fn new(program: &Vec<isize>) -> Self {
+        Self {
+            computer: AsciiComputer::new(program),
+        }
+    }
+}
+
+impl Ship for Droid {
+    fn start(&mut self) -> String {
+        self.computer.run();
+        self.computer.read().text
+    }
+
+    fn send(&mut self, command: &str) -> String {
+        self.computer.send_line(command).run();
+        self.computer.read().text
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Room {
+    name: String,
+    doors: Vec<String>,
+    items: Vec<String>,
+}
+
+// A single response can describe several rooms, e.g. when the droid gets ejected
+fn parse_rooms(output: &str) -> Vec<Room> {
+    let mut rooms: Vec<Room> = vec![];
+    let mut list: Option<&str> = None;
+
+    for line in output.lines().map(str::trim) {
+        if line.starts_with("== ") && line.ends_with(" ==") {
+            rooms.push(Room {
+                name: line[3..line.len() - 3].to_string(),
+                ..Room::default()
+            });
+            list = None;
+        } else if line == "Doors here lead:" || line == "Items here:" {
+            list = Some(line);
+        } else if line.is_empty() {
+            list = None;
+        } else if let (Some(room), Some(list), Some(entry)) =
+            (rooms.last_mut(), list, line.strip_prefix("- "))
+        {
+            if list == "Doors here lead:" {
+                room.doors.push(entry.to_string());
+            } else {
+                room.items.push(entry.to_string());
+            }
+        }
+    }
+
+    rooms
+}
+
+fn parse_password(output: &str) -> Option<String> {
+    let (_, rest) = output.split_once("by typing ")?;
+    let (password, _) = rest.split_once(" on the keypad")?;
+    Some(password.to_string())
+}
+
+fn opposite(door: &str) -> &'static str {
+    match door {
+        "north" => "south",
+        "south" => "north",
+        "east" => "west",
+        "west" => "east",
+        _ => panic!("Unknown direction {}", door),
+    }
+}
+
+struct Explorer<'a> {
+    ship: &'a mut dyn Ship,
+    // Room name to the rooms behind each of its doors
+    map: HashMap<String, HashMap<String, String>>,
+    items: Vec<String>,
+    floor_door: Option<String>,
+    password: Option<String>,
+}
+
+impl<'a> Explorer<'a> {
+    fn send(&mut self, command: &str) -> String {
+        let output = self.ship.send(command);
+        if let Some(password) = parse_password(&output) {
+            self.password = Some(password);
+        }
+        output
+    }
+
+    fn current_room(output: &str) -> Room {
+        parse_rooms(output)
+            .pop()
+            .unwrap_or_else(|| panic!("No room in the output:\n{}", output))
+    }
+
+    // Depth-first walk through every door, picking up all safe items on the way and
+    // returning to the room it started in. Stops right away once the floor lets it pass.
+    fn explore(&mut self, room: &Room) {
+        self.map.entry(room.name.clone()).or_default();
+        for item in room.items.iter() {
+            if !DEADLY_ITEMS.contains(&item.as_str()) {
+                self.send(&format!("take {}", item));
+                self.items.push(item.clone());
+            }
+        }
+
+        for door in room.doors.iter() {
+            if self.map[&room.name].contains_key(door) {
+                continue;
+            }
+
+            let output = self.send(door);
+            if self.password.is_some() {
+                return;
+            }
+            let next = Explorer::current_room(&output);
+            if next.name == room.name {
+                // The pressure-sensitive floor sent the droid back to the checkpoint
+                self.floor_door = Some(door.clone());
+                self.map
+                    .get_mut(&room.name)
+                    .unwrap()
+                    .insert(door.clone(), room.name.clone());
+                continue;
+            }
+
+            let known = self.map.contains_key(&next.name);
+            self.map
+                .get_mut(&room.name)
+                .unwrap()
+                .insert(door.clone(), next.name.clone());
+            self.map
+                .entry(next.name.clone())
+                .or_default()
+                .insert(opposite(door).to_string(), room.name.clone());
+            if !known {
+                self.explore(&next);
+                if self.password.is_some() {
+                    return;
+                }
+            }
+            self.send(opposite(door));
+        }
+    }
+
+    fn path(&self, from: &str, to: &str) -> Vec<String> {
+        let mut previous: HashMap<&str, (&str, &str)> = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+
+        while let Some(room) = queue.pop_front() {
+            if room == to {
+                break;
+            }
+            for (door, next) in self.map[room].iter() {
+                if next != from && !previous.contains_key(next.as_str()) {
+                    previous.insert(next, (room, door));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut path = vec![];
+        let mut room = to;
+        while room != from {
+            let (before, door) = previous
+                .get(room)
+                .unwrap_or_else(|| panic!("There's no way from {} to {}", from, to));
+            path.push(door.to_string());
+            room = before;
+        }
+        path.reverse();
+        path
+    }
+
+    // Visits the item subsets in Gray code order, so every attempt only takes or drops
+    // a single item. All items are carried at first.
+    fn pass_floor(&mut self, door: &str) -> String {
+        let count = self.items.len();
+        let mut carried: u64 = (1 << count) - 1;
+
+        for attempt in 0..1u64 << count {
+            let wanted = !(attempt ^ attempt >> 1) & ((1 << count) - 1);
+            for index in (0..count).filter(|index| (carried ^ wanted) & 1 << index != 0) {
+                let verb = if wanted & 1 << index != 0 {
+                    "take"
+                } else {
+                    "drop"
+                };
+                let command = format!("{} {}", verb, self.items[index]);
+                self.send(&command);
+            }
+            carried = wanted;
+
+            self.send(door);
+            if let Some(password) = self.password.take() {
+                return password;
+            }
+        }
+
+        panic!("No combination of {} items passes the checkpoint", count)
+    }
+}
+
+fn solve(ship: &mut dyn Ship) -> String {
+    let start = Explorer::current_room(&ship.start());
+    let mut explorer = Explorer {
+        ship,
+        map: HashMap::new(),
+        items: vec![],
+        floor_door: None,
+        password: None,
+    };
+
+    explorer.explore(&start);
+    if let Some(password) = explorer.password.take() {
+        return password;
+    }
+    for door in explorer.path(&start.name, CHECKPOINT) {
+        explorer.send(&door);
+    }
+    let door = explorer
+        .floor_door
+        .clone()
+        .expect("The pressure-sensitive floor was not found");
+
+    explorer.pass_floor(&door)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLOOR: &str = "Pressure-Sensitive Floor";
+
+    struct ScriptedRoom {
+        doors: Vec<(&'static str, &'static str)>,
+        items: Vec<&'static str>,
+    }
+
+    // Plays the role of the Intcode program with a tiny ship of four rooms
+    struct ScriptedShip {
+        rooms: HashMap<&'static str, ScriptedRoom>,
+        location: &'static str,
+        inventory: Vec<&'static str>,
+        weights: HashMap<&'static str, usize>,
+        required_weight: usize,
+        commands: Vec<String>,
+    }
+
+    impl ScriptedShip {
+        fn new() -> Self {
+            let room = |doors, items| ScriptedRoom { doors, items };
+            let mut rooms = HashMap::new();
+            rooms.insert(
+                "Hull Breach",
+                room(vec![("north", "Kitchen"), ("east", "Hallway")], vec![]),
+            );
+            rooms.insert(
+                "Kitchen",
+                room(vec![("south", "Hull Breach")], vec!["mug", "molten lava"]),
+            );
+            rooms.insert(
+                "Hallway",
+                room(
+                    vec![("west", "Hull Breach"), ("north", CHECKPOINT)],
+                    vec!["weather machine"],
+                ),
+            );
+            rooms.insert(
+                CHECKPOINT,
+                room(
+                    vec![("south", "Hallway"), ("east", FLOOR)],
+                    vec!["hologram"],
+                ),
+            );
+
+            let weights = [("mug", 3), ("weather machine", 5), ("hologram", 9)]
+                .iter()
+                .cloned()
+                .collect();
+            Self {
+                rooms,
+                location: "Hull Breach",
+                inventory: vec![],
+                weights,
+                required_weight: 12,
+                commands: vec![],
+            }
+        }
+
+        fn describe(&self, name: &str) -> String {
+            let room = &self.rooms[name];
+            let mut text = format!("\n\n\n== {} ==\nNothing to see here.\n\n", name);
+            text += "Doors here lead:\n";
+            for (door, _) in room.doors.iter() {
+                text += &format!("- {}\n", door);
+            }
+            if !room.items.is_empty() {
+                text += "\nItems here:\n";
+                for item in room.items.iter() {
+                    text += &format!("- {}\n", item);
+                }
+            }
+            text + "\nCommand?\n"
+        }
+
+        fn weigh(&self) -> String {
+            let weight: usize = self.inventory.iter().map(|item| self.weights[item]).sum();
+            let mut text = format!(
+                "\n\n\n== {} ==\nAnalyzing...\n\nDoors here lead:\n- west\n\n",
+                FLOOR
+            );
+            if weight == self.required_weight {
+                text += "A loud, robotic voice says \"Analysis complete! You may proceed.\" \
+                         and you enter the cockpit.\n\"Oh, hello! You should be able to get in \
+                         by typing 20250100 on the keypad at the main airlock.\"\n";
+                return text;
+            }
+
+            let comparison = if weight > self.required_weight {
+                "lighter"
+            } else {
+                "heavier"
+            };
+            text += &format!(
+                "A loud, robotic voice says \"Alert! Droids on this ship are {} than the \
+                 detected value!\" and you are ejected back to the checkpoint.\n",
+                comparison
+            );
+            text + &self.describe(CHECKPOINT)
+        }
+    }
+
+    impl Ship for ScriptedShip {
+        fn start(&mut self) -> String {
+            self.describe(self.location)
+        }
+
+        fn send(&mut self, command: &str) -> String {
+            self.commands.push(command.to_string());
+
+            if let Some(item) = command.strip_prefix("take ") {
+                assert!(!DEADLY_ITEMS.contains(&item), "Droid took {}", item);
+                let room = self.rooms.get_mut(self.location).unwrap();
+                let index = room.items.iter().position(|i| *i == item).unwrap();
+                self.inventory.push(room.items.remove(index));
+                return format!("\nYou take the {}.\n\nCommand?\n", item);
+            }
+            if let Some(item) = command.strip_prefix("drop ") {
+                let index = self.inventory.iter().position(|i| *i == item).unwrap();
+                let item = self.inventory.remove(index);
+                self.rooms.get_mut(self.location).unwrap().items.push(item);
+                return format!("\nYou drop the {}.\n\nCommand?\n", item);
+            }
+
+            let next = self.rooms[self.location]
+                .doors
+                .iter()
+                .find(|(door, _)| *door == command)
+                .map(|(_, room)| *room);
+            match next {
+                Some(FLOOR) => self.weigh(),
+                Some(room) => {
+                    self.location = room;
+                    self.describe(room)
+                }
+                None => "\nYou can't go that way.\n\nCommand?\n".to_string(),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_rooms_from_output() {
+        let mut ship = ScriptedShip::new();
+        ship.send("north");
+        let rooms = parse_rooms(&ship.describe("Kitchen"));
+
+        assert_eq!(
+            rooms,
+            vec![Room {
+                name: "Kitchen".into(),
+                doors: vec!["south".into()],
+                items: vec!["mug".into(), "molten lava".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_ejection_as_two_rooms() {
+        let mut ship = ScriptedShip::new();
+        let rooms = parse_rooms(&ship.weigh());
+
+        assert_eq!(rooms.len(), 2);
+        assert_eq!(rooms[0].name, FLOOR);
+        assert_eq!(rooms[1].name, CHECKPOINT);
+        assert_eq!(rooms[1].items, vec!["hologram"]);
+        assert_eq!(parse_password(&ship.weigh()), None);
+
+        ship.inventory = vec!["mug", "hologram"];
+        assert_eq!(parse_password(&ship.weigh()), Some("20250100".into()));
+    }
+
+    #[test]
+    fn explores_ship_and_passes_checkpoint() {
+        let mut ship = ScriptedShip::new();
+
+        assert_eq!(solve(&mut ship), "20250100");
+        assert_eq!(ship.inventory.len(), 2);
+        assert!(ship.inventory.contains(&"mug"));
+        assert!(ship.inventory.contains(&"hologram"));
+    }
+
+    #[test]
+    fn stops_exploring_once_floor_lets_droid_pass() {
+        let mut ship = ScriptedShip::new();
+        ship.required_weight = 17;
+
+        assert_eq!(solve(&mut ship), "20250100");
+        assert_eq!(ship.inventory.len(), 3);
+        assert_eq!(ship.commands.last().unwrap(), "east");
+        assert_eq!(
+            ship.commands
+                .iter()
+                .filter(|command| *command == "east")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn changes_one_item_between_attempts() {
+        let mut ship = ScriptedShip::new();
+        solve(&mut ship);
+
+        let attempts: Vec<usize> = ship
+            .commands
+            .iter()
+            .enumerate()
+            .filter(|(_, command)| *command == "east")
+            .map(|(index, _)| index)
+            .collect();
+        let floor_attempts = &attempts[attempts.len() - 4..];
+        for pair in floor_attempts.windows(2) {
+            assert_eq!(pair[1] - pair[0], 2);
+        }
+    }
+}
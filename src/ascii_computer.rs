@@ -0,0 +1,85 @@
+use crate::opcode_computer::{OpcodeComputer, StopReason};
+
+const MAX_ASCII: isize = 127;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct AsciiOutput {
+    pub text: String,
+    pub values: Vec<isize>,
+}
+
+// Talks to Intcode programs that read and write text one character per value.
+// Output values outside of the ASCII range, like final scores, are kept apart.
+pub struct AsciiComputer {
+    computer: OpcodeComputer,
+}
+
+impl AsciiComputer {
+    pub fn new(program: &Vec<isize>) -> Self {
+        Self::from_computer(OpcodeComputer::new(program))
+    }
+
+    pub fn from_computer(computer: OpcodeComputer) -> Self {
+        Self { computer }
+    }
+
+    pub fn computer(&mut self) -> &mut OpcodeComputer {
+        &mut self.computer
+    }
+
+    pub fn send_line(&mut self, line: &str) -> &mut Self {
+        for byte in line.bytes().chain("\n".bytes()) {
+            self.computer.add_input(&(byte as isize));
+        }
+        self
+    }
+
+    pub fn run(&mut self) -> StopReason {
+        self.computer.run()
+    }
+
+    pub fn read(&mut self) -> AsciiOutput {
+        let mut output = AsciiOutput::default();
+
+        for value in self.computer.get_all_output() {
+            if (0..=MAX_ASCII).contains(&value) {
+                output.text.push(value as u8 as char);
+            } else {
+                output.values.push(value);
+            }
+        }
+
+        output
+    }
+
+    pub fn halted(&self) -> bool {
+        self.computer.halted()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_lines_and_reads_text() {
+        let mut computer = AsciiComputer::new(&vec![3, 100, 4, 100, 1105, 1, 0]);
+
+        assert_eq!(computer.send_line("Hi").run(), StopReason::WaitingForInput);
+        assert_eq!(computer.read().text, "Hi\n");
+        assert_eq!(computer.read(), AsciiOutput::default());
+    }
+
+    #[test]
+    fn keeps_values_outside_of_ascii() {
+        let mut computer = AsciiComputer::new(&vec![104, 79, 104, 75, 104, 10, 104, 1000, 99]);
+        computer.run();
+
+        let expected = AsciiOutput {
+            text: "OK\n".into(),
+            values: vec![1000],
+        };
+        assert_eq!(computer.read(), expected);
+        assert!(computer.halted());
+    }
+}
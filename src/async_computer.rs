@@ -0,0 +1,219 @@
+use {
+    crate::opcode_computer::{ComputerState, OpcodeComputer},
+    futures_core::Stream,
+    std::{
+        cell::RefCell,
+        future::Future,
+        pin::Pin,
+        rc::Rc,
+        task::{Context, Poll, Waker},
+    },
+};
+
+pub struct AsyncComputer {
+    shared: Rc<RefCell<Shared>>,
+}
+
+struct Shared {
+    computer: OpcodeComputer,
+    input_waker: Option<Waker>,
+    output_waker: Option<Waker>,
+}
+
+impl Shared {
+    fn wake_output(&mut self) {
+        if let Some(waker) = self.output_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl AsyncComputer {
+    pub fn new(program: &[isize]) -> Self {
+        Self::from_computer(OpcodeComputer::new(&program.to_vec()))
+    }
+
+    pub fn from_computer(computer: OpcodeComputer) -> Self {
+        Self {
+            shared: Rc::new(RefCell::new(Shared {
+                computer,
+                input_waker: None,
+                output_waker: None,
+            })),
+        }
+    }
+
+    pub fn input(&self) -> InputSender {
+        InputSender {
+            shared: self.shared.clone(),
+        }
+    }
+
+    pub fn outputs(&self) -> Outputs {
+        Outputs {
+            shared: self.shared.clone(),
+        }
+    }
+
+    pub fn run(&self) -> Execution {
+        Execution {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct InputSender {
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl InputSender {
+    pub fn send(&self, value: isize) {
+        let mut shared = self.shared.borrow_mut();
+        shared.computer.add_input(&value);
+        if let Some(waker) = shared.input_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+pub struct Execution {
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Future for Execution {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let mut shared = self.shared.borrow_mut();
+        shared.computer.run();
+        // Also wakes the outputs when the computer finished, so the stream can end
+        shared.wake_output();
+
+        match shared.computer.state() {
+            ComputerState::WaitingForInput(_) => {
+                shared.input_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+            _ => Poll::Ready(()),
+        }
+    }
+}
+
+pub struct Outputs {
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Outputs {
+    pub fn next_value(&mut self) -> Next<'_> {
+        Next { outputs: self }
+    }
+}
+
+impl Stream for Outputs {
+    type Item = isize;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<isize>> {
+        let mut shared = self.shared.borrow_mut();
+
+        match shared.computer.get_output() {
+            Some(value) => Poll::Ready(Some(value)),
+            None if shared.computer.finished() => Poll::Ready(None),
+            None => {
+                shared.output_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+pub struct Next<'a> {
+    outputs: &'a mut Outputs,
+}
+
+impl Future for Next<'_> {
+    type Output = Option<isize>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<isize>> {
+        Pin::new(&mut *self.outputs).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::executor::{block_on, LocalExecutor},
+    };
+
+    #[test]
+    fn resolves_input_when_value_is_pushed() {
+        let computer = AsyncComputer::new(&[3, 0, 4, 0, 99]);
+        let mut outputs = computer.outputs();
+        let input = computer.input();
+        let mut executor = LocalExecutor::default();
+
+        executor
+            .spawn(computer.run())
+            .spawn(async move { input.send(7) });
+        executor.run();
+
+        assert_eq!(block_on(outputs.next_value()), Some(7));
+        assert_eq!(block_on(outputs.next_value()), None);
+    }
+
+    #[test]
+    fn ends_outputs_when_program_faults() {
+        let computer = AsyncComputer::new(&[104, 1, 1, -1, 0, 0, 99]);
+        let mut outputs = computer.outputs();
+        let values = Rc::new(RefCell::new(vec![]));
+        let collected = values.clone();
+        let mut executor = LocalExecutor::default();
+
+        executor
+            .spawn(async move {
+                while let Some(value) = outputs.next_value().await {
+                    collected.borrow_mut().push(value);
+                }
+            })
+            .spawn(computer.run());
+        executor.run();
+
+        assert_eq!(*values.borrow(), vec![1]);
+    }
+
+    #[test]
+    fn chains_amplifiers_in_a_feedback_loop() {
+        let program = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let amplifiers: Vec<_> = [9, 8, 7, 6, 5]
+            .iter()
+            .map(|phase| {
+                let amplifier = AsyncComputer::new(&program);
+                amplifier.input().send(*phase);
+                amplifier
+            })
+            .collect();
+        let signal = Rc::new(RefCell::new(0));
+        let mut executor = LocalExecutor::default();
+
+        amplifiers[0].input().send(0);
+        for (index, amplifier) in amplifiers.iter().enumerate() {
+            let mut outputs = amplifier.outputs();
+            let next = amplifiers[(index + 1) % amplifiers.len()].input();
+            let signal = signal.clone();
+
+            executor.spawn(amplifier.run()).spawn(async move {
+                while let Some(value) = outputs.next_value().await {
+                    *signal.borrow_mut() = value;
+                    next.send(value);
+                }
+            });
+        }
+        executor.run();
+
+        assert_eq!(*signal.borrow(), 139629729);
+    }
+}
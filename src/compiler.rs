@@ -0,0 +1,1304 @@
+use std::{collections::HashMap, fmt};
+
+const KEYWORDS: [&str; 6] = ["fn", "let", "if", "else", "while", "return"];
+
+const SYMBOLS: [&str; 23] = [
+    "<=", ">=", "==", "!=", "&&", "||", "(", ")", "{", "}", "[", "]", ",", ";", "=", "+", "-", "*",
+    "/", "%", "<", ">", "!",
+];
+
+// Intcode has no division, so `/` and `%` are compiled to calls of these functions.
+// Division by zero never returns.
+const PRELUDE: &str = "
+fn __div(a, b) {
+    let negative = 0;
+    if a < 0 { a = -a; negative = 1 - negative; }
+    if b < 0 { b = -b; negative = 1 - negative; }
+    let quotient = 0;
+    while a >= b {
+        let step = b;
+        let multiple = 1;
+        while step + step <= a {
+            step = step + step;
+            multiple = multiple + multiple;
+        }
+        a = a - step;
+        quotient = quotient + multiple;
+    }
+    if negative { return -quotient; }
+    return quotient;
+}
+
+fn __mod(a, b) {
+    return a - __div(a, b) * b;
+}
+";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompileError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl CompileError {
+    fn new(line: usize, message: String) -> Self {
+        Self { line, message }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+pub fn compile(source: &str) -> Result<Vec<isize>, CompileError> {
+    let mut lexemes = tokenize(source)?;
+    let divides = lexemes
+        .iter()
+        .any(|lexeme| lexeme.token == Token::Symbol("/") || lexeme.token == Token::Symbol("%"));
+    if divides {
+        lexemes.extend(tokenize(PRELUDE)?);
+    }
+
+    let program = Parser {
+        lexemes,
+        position: 0,
+    }
+    .program()?;
+    Generator::default().generate(&program)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(isize),
+    Name(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{}", value),
+            Token::Name(name) => write!(f, "'{}'", name),
+            Token::Symbol(symbol) => write!(f, "'{}'", symbol),
+        }
+    }
+}
+
+struct Lexeme {
+    token: Token,
+    line: usize,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Lexeme>, CompileError> {
+    let mut lexemes = vec![];
+
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let mut rest = text.split("//").next().unwrap().trim_start();
+
+        while let Some(first) = rest.chars().next() {
+            let length;
+            let token = if first.is_ascii_digit() {
+                length = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let value = rest[..length].parse().map_err(|_| {
+                    CompileError::new(line, format!("Number {} is too large", &rest[..length]))
+                })?;
+                Token::Number(value)
+            } else if first.is_ascii_alphabetic() || first == '_' {
+                length = rest
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                Token::Name(rest[..length].to_string())
+            } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+                length = symbol.len();
+                Token::Symbol(symbol)
+            } else {
+                return Err(CompileError::new(
+                    line,
+                    format!("Unexpected character '{}'", first),
+                ));
+            };
+
+            lexemes.push(Lexeme { token, line });
+            rest = rest[length..].trim_start();
+        }
+    }
+
+    Ok(lexemes)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+const PRECEDENCE: [&[(&str, BinaryOp)]; 6] = [
+    &[("||", BinaryOp::Or)],
+    &[("&&", BinaryOp::And)],
+    &[("==", BinaryOp::Equal), ("!=", BinaryOp::NotEqual)],
+    &[
+        ("<", BinaryOp::Less),
+        ("<=", BinaryOp::LessEqual),
+        (">", BinaryOp::Greater),
+        (">=", BinaryOp::GreaterEqual),
+    ],
+    &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+    &[
+        ("*", BinaryOp::Mul),
+        ("/", BinaryOp::Div),
+        ("%", BinaryOp::Mod),
+    ],
+];
+
+#[derive(Debug)]
+enum Expr {
+    Number(isize),
+    Variable(String),
+    Index(String, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Negate(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn has_side_effects(&self) -> bool {
+        match self {
+            Expr::Number(_) | Expr::Variable(_) => false,
+            Expr::Call(_, _) => true,
+            Expr::Index(_, index) => index.has_side_effects(),
+            Expr::Negate(operand) | Expr::Not(operand) => operand.has_side_effects(),
+            Expr::Binary(_, left, right) => left.has_side_effects() || right.has_side_effects(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Statement {
+    Let(String, Expr),
+    Assign(Expr, Expr),
+    If(Expr, Vec<Line>, Vec<Line>),
+    While(Expr, Vec<Line>),
+    Return(Option<Expr>),
+    Expression(Expr),
+}
+
+#[derive(Debug)]
+struct Line {
+    number: usize,
+    statement: Statement,
+}
+
+enum Global {
+    Scalar(isize),
+    Array(usize),
+}
+
+struct Function {
+    name: String,
+    params: Vec<String>,
+    body: Vec<Line>,
+    line: usize,
+}
+
+struct Program {
+    globals: Vec<(String, Global, usize)>,
+    functions: Vec<Function>,
+}
+
+struct Parser {
+    lexemes: Vec<Lexeme>,
+    position: usize,
+}
+
+impl Parser {
+    fn program(&mut self) -> Result<Program, CompileError> {
+        let mut program = Program {
+            globals: vec![],
+            functions: vec![],
+        };
+
+        while self.position < self.lexemes.len() {
+            let line = self.line();
+            if self.eat("fn") {
+                program.functions.push(self.function(line)?);
+            } else if self.eat("let") {
+                let name = self.name()?;
+                let global = if self.eat("[") {
+                    let size = self.number()?;
+                    self.expect("]")?;
+                    Global::Array(size as usize)
+                } else {
+                    self.expect("=")?;
+                    let negative = self.eat("-");
+                    let value = self.number()?;
+                    Global::Scalar(if negative { -value } else { value })
+                };
+                self.expect(";")?;
+                program.globals.push((name, global, line));
+            } else {
+                return Err(self.unexpected("'fn' or 'let'"));
+            }
+        }
+
+        Ok(program)
+    }
+
+    fn function(&mut self, line: usize) -> Result<Function, CompileError> {
+        let name = self.name()?;
+        self.expect("(")?;
+        let mut params = vec![];
+        while !self.eat(")") {
+            if !params.is_empty() {
+                self.expect(",")?;
+            }
+            params.push(self.name()?);
+        }
+
+        Ok(Function {
+            name,
+            params,
+            body: self.block()?,
+            line,
+        })
+    }
+
+    fn block(&mut self) -> Result<Vec<Line>, CompileError> {
+        self.expect("{")?;
+        let mut lines = vec![];
+        while !self.eat("}") {
+            lines.push(self.line_of_code()?);
+        }
+        Ok(lines)
+    }
+
+    fn line_of_code(&mut self) -> Result<Line, CompileError> {
+        let number = self.line();
+
+        let statement = if self.eat("let") {
+            let name = self.name()?;
+            self.expect("=")?;
+            let value = self.expression()?;
+            self.expect(";")?;
+            Statement::Let(name, value)
+        } else if self.eat("if") {
+            self.if_statement()?
+        } else if self.eat("while") {
+            Statement::While(self.expression()?, self.block()?)
+        } else if self.eat("return") {
+            let value = if self.is(";") {
+                None
+            } else {
+                Some(self.expression()?)
+            };
+            self.expect(";")?;
+            Statement::Return(value)
+        } else {
+            let expression = self.expression()?;
+            let statement = if self.eat("=") {
+                match expression {
+                    Expr::Variable(_) | Expr::Index(_, _) => {
+                        Statement::Assign(expression, self.expression()?)
+                    }
+                    _ => return Err(CompileError::new(number, "Cannot assign to this".into())),
+                }
+            } else {
+                Statement::Expression(expression)
+            };
+            self.expect(";")?;
+            statement
+        };
+
+        Ok(Line { number, statement })
+    }
+
+    fn if_statement(&mut self) -> Result<Statement, CompileError> {
+        let condition = self.expression()?;
+        let then = self.block()?;
+        let otherwise = if !self.eat("else") {
+            vec![]
+        } else if self.is("if") {
+            vec![self.line_of_code()?]
+        } else {
+            self.block()?
+        };
+        Ok(Statement::If(condition, then, otherwise))
+    }
+
+    fn expression(&mut self) -> Result<Expr, CompileError> {
+        self.binary(0)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, CompileError> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+
+        let mut left = self.binary(level + 1)?;
+        'operators: loop {
+            for (symbol, op) in PRECEDENCE[level] {
+                if self.eat(symbol) {
+                    let right = self.binary(level + 1)?;
+                    left = Expr::Binary(*op, Box::new(left), Box::new(right));
+                    continue 'operators;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, CompileError> {
+        if self.eat("-") {
+            return Ok(match self.unary()? {
+                Expr::Number(value) => Expr::Number(-value),
+                operand => Expr::Negate(Box::new(operand)),
+            });
+        }
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, CompileError> {
+        if self.eat("(") {
+            let expression = self.expression()?;
+            self.expect(")")?;
+            return Ok(expression);
+        }
+        if let Some(Token::Number(_)) = self.peek() {
+            return Ok(Expr::Number(self.number()?));
+        }
+
+        let name = self.name()?;
+        if self.eat("(") {
+            let mut args = vec![];
+            while !self.eat(")") {
+                if !args.is_empty() {
+                    self.expect(",")?;
+                }
+                args.push(self.expression()?);
+            }
+            Ok(Expr::Call(name, args))
+        } else if self.eat("[") {
+            let index = self.expression()?;
+            self.expect("]")?;
+            Ok(Expr::Index(name, Box::new(index)))
+        } else {
+            Ok(Expr::Variable(name))
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.lexemes.get(self.position).map(|lexeme| &lexeme.token)
+    }
+
+    fn line(&self) -> usize {
+        self.lexemes
+            .get(self.position)
+            .or_else(|| self.lexemes.last())
+            .map_or(1, |lexeme| lexeme.line)
+    }
+
+    fn is(&self, expected: &str) -> bool {
+        match self.peek() {
+            Some(Token::Symbol(symbol)) => *symbol == expected,
+            Some(Token::Name(name)) => name == expected,
+            _ => false,
+        }
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        let found = self.is(expected);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), CompileError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", expected)))
+        }
+    }
+
+    fn name(&mut self) -> Result<String, CompileError> {
+        match self.peek() {
+            Some(Token::Name(name)) if !KEYWORDS.contains(&name.as_str()) => {
+                let name = name.clone();
+                self.position += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected("a name")),
+        }
+    }
+
+    fn number(&mut self) -> Result<isize, CompileError> {
+        match self.peek() {
+            Some(Token::Number(value)) => {
+                let value = *value;
+                self.position += 1;
+                Ok(value)
+            }
+            _ => Err(self.unexpected("a number")),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> CompileError {
+        let found = self
+            .peek()
+            .map_or("end of input".to_string(), |token| token.to_string());
+        CompileError::new(
+            self.line(),
+            format!("Expected {} but found {}", expected, found),
+        )
+    }
+}
+
+type Label = usize;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Word {
+    Value(isize),
+    Label(Label, isize),
+    Negated(Label),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Operand {
+    mode: isize,
+    word: Word,
+}
+
+fn positional(word: Word) -> Operand {
+    Operand { mode: 0, word }
+}
+
+fn immediate(word: Word) -> Operand {
+    Operand { mode: 1, word }
+}
+
+fn relative(word: Word) -> Operand {
+    Operand { mode: 2, word }
+}
+
+const ZERO: Operand = Operand {
+    mode: 1,
+    word: Word::Value(0),
+};
+
+const ONE: Operand = Operand {
+    mode: 1,
+    word: Word::Value(1),
+};
+
+// Frame layout relative to the base: return address, return value, params, locals, temporaries.
+// A frame's size is a label, as it's only known once the whole function is generated.
+struct Frame {
+    size: Label,
+    // Innermost block last, the outermost one holds the params
+    scopes: Vec<HashMap<String, isize>>,
+    next_local: isize,
+    temporaries: isize,
+    depth: isize,
+    line: usize,
+}
+
+enum Symbol {
+    Scalar(Label),
+    Array(Label),
+}
+
+#[derive(Default)]
+struct Generator {
+    code: Vec<Word>,
+    labels: Vec<Option<isize>>,
+    globals: HashMap<String, Symbol>,
+    functions: HashMap<String, (Label, usize)>,
+    frame: Option<Frame>,
+}
+
+impl Generator {
+    fn generate(mut self, program: &Program) -> Result<Vec<isize>, CompileError> {
+        for (name, global, line) in program.globals.iter() {
+            let label = self.label();
+            let symbol = match global {
+                Global::Scalar(_) => Symbol::Scalar(label),
+                Global::Array(_) => Symbol::Array(label),
+            };
+            if self.globals.insert(name.clone(), symbol).is_some() {
+                return Err(CompileError::new(
+                    *line,
+                    format!("Global {} is defined twice", name),
+                ));
+            }
+        }
+        for function in program.functions.iter() {
+            if ["read", "print"].contains(&function.name.as_str()) {
+                return Err(CompileError::new(
+                    function.line,
+                    format!("{} is a builtin function", function.name),
+                ));
+            }
+            let entry = (self.label(), function.params.len());
+            if self
+                .functions
+                .insert(function.name.clone(), entry)
+                .is_some()
+            {
+                return Err(CompileError::new(
+                    function.line,
+                    format!("Function {} is defined twice", function.name),
+                ));
+            }
+        }
+
+        let main = match self.functions.get("main") {
+            Some((label, 0)) => *label,
+            Some(_) => return Err(CompileError::new(1, "main cannot take params".into())),
+            None => return Err(CompileError::new(1, "Missing main function".into())),
+        };
+        let stack = self.label();
+        let exit = self.label();
+        self.emit(9, &[immediate(Word::Label(stack, 0))]);
+        self.emit(
+            1,
+            &[
+                immediate(Word::Label(exit, 0)),
+                ZERO,
+                relative(Word::Value(0)),
+            ],
+        );
+        self.emit(5, &[ONE, immediate(Word::Label(main, 0))]);
+        self.place(exit);
+        self.emit(99, &[]);
+
+        for function in program.functions.iter() {
+            self.function(function)?;
+        }
+
+        for (name, global, _) in program.globals.iter() {
+            match (global, &self.globals[name]) {
+                (Global::Scalar(value), Symbol::Scalar(label)) => {
+                    self.place(*label);
+                    self.code.push(Word::Value(*value));
+                }
+                (Global::Array(size), Symbol::Array(label)) => {
+                    self.place(*label);
+                    self.code.extend(vec![Word::Value(0); *size]);
+                }
+                _ => unreachable!(),
+            }
+        }
+        self.place(stack);
+
+        Ok(self.code.iter().map(|word| self.resolve(*word)).collect())
+    }
+
+    fn function(&mut self, function: &Function) -> Result<(), CompileError> {
+        let mut params = HashMap::new();
+        for param in function.params.iter() {
+            let slot = params.len() as isize + 2;
+            if params.insert(param.clone(), slot).is_some() {
+                return Err(CompileError::new(
+                    function.line,
+                    format!("Param {} is declared twice", param),
+                ));
+            }
+        }
+
+        self.place(self.functions[&function.name].0);
+        let size = self.label();
+        // Every let gets its own slot, so blocks never share one
+        let next_local = params.len() as isize + 2;
+        self.frame = Some(Frame {
+            size,
+            temporaries: next_local + count_locals(&function.body),
+            scopes: vec![params],
+            next_local,
+            depth: 0,
+            line: function.line,
+        });
+
+        for line in function.body.iter() {
+            self.line(line)?;
+        }
+        self.return_value(ZERO);
+
+        let frame = self.frame.take().unwrap();
+        self.define(frame.size, frame.temporaries + frame.depth);
+        Ok(())
+    }
+
+    fn block(&mut self, lines: &[Line]) -> Result<(), CompileError> {
+        self.frame().scopes.push(HashMap::new());
+        for line in lines {
+            self.line(line)?;
+        }
+        self.frame().scopes.pop();
+        Ok(())
+    }
+
+    fn line(&mut self, line: &Line) -> Result<(), CompileError> {
+        self.frame().line = line.number;
+
+        match &line.statement {
+            Statement::Let(name, value) => {
+                // The value still sees an outer variable with the same name
+                let value = self.expression(value, 0)?;
+                let target = self.declare(name)?;
+                self.copy(value, target);
+            }
+            Statement::Assign(target, value) => self.assign(target, value)?,
+            Statement::If(condition, then, otherwise) => {
+                let skip = self.label();
+                let end = self.label();
+                let condition = self.expression(condition, 0)?;
+                self.emit(6, &[condition, immediate(Word::Label(skip, 0))]);
+                self.block(then)?;
+                if !otherwise.is_empty() {
+                    self.emit(5, &[ONE, immediate(Word::Label(end, 0))]);
+                }
+                self.place(skip);
+                self.block(otherwise)?;
+                self.place(end);
+            }
+            Statement::While(condition, body) => {
+                let start = self.label();
+                let end = self.label();
+                self.place(start);
+                let condition = self.expression(condition, 0)?;
+                self.emit(6, &[condition, immediate(Word::Label(end, 0))]);
+                self.block(body)?;
+                self.emit(5, &[ONE, immediate(Word::Label(start, 0))]);
+                self.place(end);
+            }
+            Statement::Return(value) => {
+                let value = match value {
+                    Some(value) => self.expression(value, 0)?,
+                    None => ZERO,
+                };
+                self.return_value(value);
+            }
+            Statement::Expression(expression) => {
+                self.expression(expression, 0)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn assign(&mut self, target: &Expr, value: &Expr) -> Result<(), CompileError> {
+        match target {
+            Expr::Variable(name) => {
+                let target = self.variable(name)?;
+                let value = self.expression(value, 0)?;
+                self.copy(value, target);
+            }
+            Expr::Index(name, index) => {
+                let array = self.array(name)?;
+                let index = self.expression(index, 0)?;
+                let index = self.materialize(index, 0);
+                let value = self.expression(value, 1)?;
+                // Store the element address into the target of the following instruction
+                let patched = self.code.len() + 4 + 3;
+                self.emit(
+                    1,
+                    &[array, index, positional(Word::Value(patched as isize))],
+                );
+                self.emit(1, &[value, ZERO, positional(Word::Value(0))]);
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &Expr, depth: isize) -> Result<Operand, CompileError> {
+        match expression {
+            Expr::Number(value) => Ok(immediate(Word::Value(*value))),
+            Expr::Variable(name) => self.variable(name),
+            Expr::Index(name, index) => {
+                let array = self.array(name)?;
+                let index = self.expression(index, depth)?;
+                let result = self.temporary(depth);
+                // Store the element address into the source of the following instruction
+                let patched = self.code.len() + 4 + 1;
+                self.emit(
+                    1,
+                    &[array, index, positional(Word::Value(patched as isize))],
+                );
+                self.emit(1, &[positional(Word::Value(0)), ZERO, result]);
+                Ok(result)
+            }
+            Expr::Call(name, args) => self.call(name, args, depth),
+            Expr::Negate(operand) => {
+                let operand = self.expression(operand, depth)?;
+                let result = self.temporary(depth);
+                self.emit(2, &[operand, immediate(Word::Value(-1)), result]);
+                Ok(result)
+            }
+            Expr::Not(operand) => {
+                let operand = self.expression(operand, depth)?;
+                let result = self.temporary(depth);
+                self.emit(8, &[operand, ZERO, result]);
+                Ok(result)
+            }
+            Expr::Binary(BinaryOp::Div, left, right) => {
+                self.prelude_call("__div", left, right, depth)
+            }
+            Expr::Binary(BinaryOp::Mod, left, right) => {
+                self.prelude_call("__mod", left, right, depth)
+            }
+            Expr::Binary(op @ BinaryOp::And, left, right)
+            | Expr::Binary(op @ BinaryOp::Or, left, right) => {
+                let end = self.label();
+                let result = self.truth(left, depth)?;
+                let jump = if *op == BinaryOp::And { 6 } else { 5 };
+                self.emit(jump, &[result, immediate(Word::Label(end, 0))]);
+                let right = self.truth(right, depth + 1)?;
+                self.copy(right, result);
+                self.place(end);
+                Ok(result)
+            }
+            Expr::Binary(op, left, right) => {
+                let mut left = self.expression(left, depth)?;
+                if right.has_side_effects() {
+                    left = self.materialize(left, depth);
+                }
+                let right = self.expression(right, depth + 1)?;
+                let result = self.temporary(depth);
+                self.binary(*op, left, right, result, depth);
+                Ok(result)
+            }
+        }
+    }
+
+    fn binary(
+        &mut self,
+        op: BinaryOp,
+        left: Operand,
+        right: Operand,
+        result: Operand,
+        depth: isize,
+    ) {
+        match op {
+            BinaryOp::Add => self.emit(1, &[left, right, result]),
+            BinaryOp::Sub => {
+                let negated = self.temporary(depth + 1);
+                self.emit(2, &[right, immediate(Word::Value(-1)), negated]);
+                self.emit(1, &[left, negated, result]);
+            }
+            BinaryOp::Mul => self.emit(2, &[left, right, result]),
+            BinaryOp::Less => self.emit(7, &[left, right, result]),
+            BinaryOp::Greater => self.emit(7, &[right, left, result]),
+            BinaryOp::LessEqual => {
+                self.emit(7, &[right, left, result]);
+                self.emit(8, &[result, ZERO, result]);
+            }
+            BinaryOp::GreaterEqual => {
+                self.emit(7, &[left, right, result]);
+                self.emit(8, &[result, ZERO, result]);
+            }
+            BinaryOp::Equal => self.emit(8, &[left, right, result]),
+            BinaryOp::NotEqual => {
+                self.emit(8, &[left, right, result]);
+                self.emit(8, &[result, ZERO, result]);
+            }
+            BinaryOp::And | BinaryOp::Or | BinaryOp::Div | BinaryOp::Mod => unreachable!(),
+        }
+    }
+
+    fn truth(&mut self, expression: &Expr, depth: isize) -> Result<Operand, CompileError> {
+        let value = self.expression(expression, depth)?;
+        let result = self.temporary(depth);
+        self.emit(8, &[value, ZERO, result]);
+        self.emit(8, &[result, ZERO, result]);
+        Ok(result)
+    }
+
+    fn prelude_call(
+        &mut self,
+        name: &str,
+        left: &Expr,
+        right: &Expr,
+        depth: isize,
+    ) -> Result<Operand, CompileError> {
+        let (label, _) = self.functions[name];
+        let left = self.expression(left, depth)?;
+        let left = self.materialize(left, depth);
+        let right = self.expression(right, depth + 1)?;
+        let right = self.materialize(right, depth + 1);
+        Ok(self.invoke(label, &[left, right], depth))
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr], depth: isize) -> Result<Operand, CompileError> {
+        let expected = match name {
+            "read" => Some(0),
+            "print" => Some(1),
+            _ => self.functions.get(name).map(|(_, arity)| *arity),
+        };
+        match expected {
+            None => return Err(self.error(format!("Unknown function {}", name))),
+            Some(arity) if arity != args.len() => {
+                return Err(self.error(format!(
+                    "Function {} expects {} arguments but got {}",
+                    name,
+                    arity,
+                    args.len()
+                )))
+            }
+            _ => (),
+        }
+
+        match name {
+            "read" => {
+                let result = self.temporary(depth);
+                self.emit(3, &[result]);
+                Ok(result)
+            }
+            "print" => {
+                let value = self.expression(&args[0], depth)?;
+                self.emit(4, &[value]);
+                Ok(ZERO)
+            }
+            _ => {
+                let mut values = vec![];
+                for (offset, arg) in args.iter().enumerate() {
+                    let value = self.expression(arg, depth + offset as isize)?;
+                    values.push(self.materialize(value, depth + offset as isize));
+                }
+                Ok(self.invoke(self.functions[name].0, &values, depth))
+            }
+        }
+    }
+
+    fn invoke(&mut self, function: Label, args: &[Operand], depth: isize) -> Operand {
+        let size = self.frame().size;
+        for (offset, arg) in args.iter().enumerate() {
+            self.copy(*arg, relative(Word::Label(size, offset as isize + 2)));
+        }
+
+        let back = self.label();
+        self.emit(
+            1,
+            &[
+                immediate(Word::Label(back, 0)),
+                ZERO,
+                relative(Word::Label(size, 0)),
+            ],
+        );
+        self.emit(9, &[immediate(Word::Label(size, 0))]);
+        self.emit(5, &[ONE, immediate(Word::Label(function, 0))]);
+        self.place(back);
+        self.emit(9, &[immediate(Word::Negated(size))]);
+
+        let result = self.temporary(depth);
+        self.copy(relative(Word::Label(size, 1)), result);
+        result
+    }
+
+    fn return_value(&mut self, value: Operand) {
+        self.copy(value, relative(Word::Value(1)));
+        self.emit(5, &[ONE, relative(Word::Value(0))]);
+    }
+
+    fn declare(&mut self, name: &str) -> Result<Operand, CompileError> {
+        let frame = self.frame();
+        let slot = frame.next_local;
+        if frame
+            .scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.into(), slot)
+            .is_some()
+        {
+            return Err(self.error(format!("Variable {} is declared twice in one block", name)));
+        }
+        self.frame().next_local += 1;
+        Ok(relative(Word::Value(slot)))
+    }
+
+    fn local(&mut self, name: &str) -> Option<isize> {
+        self.frame()
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).cloned())
+    }
+
+    fn variable(&mut self, name: &str) -> Result<Operand, CompileError> {
+        if let Some(slot) = self.local(name) {
+            return Ok(relative(Word::Value(slot)));
+        }
+        match self.globals.get(name) {
+            Some(Symbol::Scalar(label)) => Ok(positional(Word::Label(*label, 0))),
+            Some(Symbol::Array(_)) => Err(self.error(format!("{} is an array", name))),
+            None => Err(self.error(format!("Unknown variable {}", name))),
+        }
+    }
+
+    fn array(&mut self, name: &str) -> Result<Operand, CompileError> {
+        let local = self.local(name).is_some();
+        match self.globals.get(name) {
+            Some(Symbol::Array(label)) if !local => Ok(immediate(Word::Label(*label, 0))),
+            _ => Err(self.error(format!("{} is not an array", name))),
+        }
+    }
+
+    fn temporary(&mut self, depth: isize) -> Operand {
+        let frame = self.frame();
+        frame.depth = isize::max(frame.depth, depth + 1);
+        relative(Word::Value(frame.temporaries + depth))
+    }
+
+    fn materialize(&mut self, value: Operand, depth: isize) -> Operand {
+        let temporary = self.temporary(depth);
+        self.copy(value, temporary);
+        temporary
+    }
+
+    fn copy(&mut self, from: Operand, to: Operand) {
+        if from != to {
+            self.emit(1, &[from, ZERO, to]);
+        }
+    }
+
+    fn emit(&mut self, opcode: isize, operands: &[Operand]) {
+        let modes: isize = operands
+            .iter()
+            .enumerate()
+            .map(|(position, operand)| operand.mode * 10_isize.pow(position as u32 + 2))
+            .sum();
+        self.code.push(Word::Value(opcode + modes));
+        self.code
+            .extend(operands.iter().map(|operand| operand.word));
+    }
+
+    fn label(&mut self) -> Label {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn place(&mut self, label: Label) {
+        self.define(label, self.code.len() as isize);
+    }
+
+    fn define(&mut self, label: Label, value: isize) {
+        self.labels[label] = Some(value);
+    }
+
+    fn resolve(&self, word: Word) -> isize {
+        match word {
+            Word::Value(value) => value,
+            Word::Label(label, offset) => self.labels[label].unwrap() + offset,
+            Word::Negated(label) => -self.labels[label].unwrap(),
+        }
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frame.as_mut().unwrap()
+    }
+
+    fn error(&mut self, message: String) -> CompileError {
+        CompileError::new(self.frame().line, message)
+    }
+}
+
+fn count_locals(lines: &[Line]) -> isize {
+    lines
+        .iter()
+        .map(|line| match &line.statement {
+            Statement::Let(_, _) => 1,
+            Statement::If(_, then, otherwise) => count_locals(then) + count_locals(otherwise),
+            Statement::While(_, body) => count_locals(body),
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::opcode_computer::OpcodeComputer};
+
+    fn run(source: &str, input: &[isize]) -> Vec<isize> {
+        let image = compile(source).unwrap();
+        let mut computer = OpcodeComputer::new(&image);
+        for value in input {
+            computer.add_input(value);
+        }
+        computer.run();
+        assert!(computer.halted());
+        computer.get_all_output()
+    }
+
+    #[test]
+    fn prints_arithmetic() {
+        let source = "
+            fn main() {
+                let x = 7;
+                print(x * 6 - -3 + 1);
+                print(-x);
+                print(2 - 3 - 4);
+            }
+        ";
+        assert_eq!(run(source, &[]), vec![46, -7, -5]);
+    }
+
+    #[test]
+    fn reads_input() {
+        let source = "fn main() { print(read() - read()); }";
+        assert_eq!(run(source, &[10, 3]), vec![7]);
+    }
+
+    #[test]
+    fn compares_and_branches() {
+        let source = "
+            fn sign(x) {
+                if x < 0 { return -1; } else if x == 0 { return 0; }
+                return 1;
+            }
+
+            fn main() {
+                print(sign(-5));
+                print(sign(0));
+                print(sign(5));
+                print(3 <= 3);
+                print(3 >= 4);
+                print(3 != 4);
+                print(!0);
+            }
+        ";
+        assert_eq!(run(source, &[]), vec![-1, 0, 1, 1, 0, 1, 1]);
+    }
+
+    #[test]
+    fn short_circuits_logical_operators() {
+        let source = "
+            let calls = 0;
+
+            fn touch(value) {
+                calls = calls + 1;
+                return value;
+            }
+
+            fn main() {
+                print(touch(0) && touch(1));
+                print(touch(5) || touch(1));
+                print(touch(5) && touch(7));
+                print(calls);
+            }
+        ";
+        assert_eq!(run(source, &[]), vec![0, 1, 1, 4]);
+    }
+
+    #[test]
+    fn recurses_through_relative_base() {
+        let source = "
+            fn fact(n) {
+                if n < 2 { return 1; }
+                return n * fact(n - 1);
+            }
+
+            fn fib(n) {
+                if n < 2 { return n; }
+                return fib(n - 1) + fib(n - 2);
+            }
+
+            fn main() {
+                print(fact(read()));
+                print(fib(15));
+            }
+        ";
+        assert_eq!(run(source, &[10]), vec![3628800, 610]);
+    }
+
+    #[test]
+    fn divides_like_rust() {
+        let source = "
+            fn main() {
+                print(17 / 5);
+                print(17 % 5);
+                print(-17 / 5);
+                print(-17 % 5);
+                print(17 / -5);
+                print(1000000007 % 97);
+            }
+        ";
+        assert_eq!(
+            run(source, &[]),
+            vec![17 / 5, 17 % 5, -17 / 5, -17 % 5, 17 / -5, 1000000007 % 97]
+        );
+    }
+
+    #[test]
+    fn sorts_global_array() {
+        let source = "
+            let values[10];
+
+            fn main() {
+                let count = read();
+                let i = 0;
+                while i < count {
+                    values[i] = read();
+                    i = i + 1;
+                }
+
+                let sorted = 0;
+                while !sorted {
+                    sorted = 1;
+                    i = 1;
+                    while i < count {
+                        if values[i - 1] > values[i] {
+                            let swap = values[i];
+                            values[i] = values[i - 1];
+                            values[i - 1] = swap;
+                            sorted = 0;
+                        }
+                        i = i + 1;
+                    }
+                }
+
+                i = 0;
+                while i < count {
+                    print(values[i]);
+                    i = i + 1;
+                }
+            }
+        ";
+        assert_eq!(
+            run(source, &[6, 5, -2, 9, 0, 5, 1]),
+            vec![-2, 0, 1, 5, 5, 9]
+        );
+    }
+
+    #[test]
+    fn sieves_primes() {
+        let source = "
+            let composite[50];
+
+            fn main() {
+                let n = 2;
+                while n < 50 {
+                    if !composite[n] {
+                        print(n);
+                        let multiple = n * n;
+                        while multiple < 50 {
+                            composite[multiple] = 1;
+                            multiple = multiple + n;
+                        }
+                    }
+                    n = n + 1;
+                }
+            }
+        ";
+        assert_eq!(
+            run(source, &[]),
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47]
+        );
+    }
+
+    #[test]
+    fn keeps_globals_between_calls() {
+        let source = "
+            let counter = -3;
+
+            fn bump() { counter = counter + 1; }
+
+            fn main() {
+                bump();
+                bump();
+                print(counter);
+            }
+        ";
+        assert_eq!(run(source, &[]), vec![-1]);
+    }
+
+    #[test]
+    fn scopes_locals_to_their_block() {
+        let source = "
+            fn main() {
+                let a = 3;
+                if a {
+                    let a = a + 1;
+                    print(a);
+                    while a < 6 {
+                        let b = a * 10;
+                        a = a + 1;
+                        print(b);
+                    }
+                }
+                let b = 7;
+                print(a);
+                print(b);
+            }
+        ";
+        assert_eq!(run(source, &[]), vec![4, 40, 50, 3, 7]);
+    }
+
+    #[test]
+    fn rejects_undeclared_and_redeclared_locals() {
+        let error = |source| compile(source).unwrap_err().to_string();
+
+        assert_eq!(
+            error("fn main() {\n  print(a);\n  let a = 1;\n}"),
+            "line 2: Unknown variable a"
+        );
+        assert_eq!(
+            error("fn main() {\n  if 1 { let a = 1; }\n  print(a);\n}"),
+            "line 3: Unknown variable a"
+        );
+        assert_eq!(
+            error("fn main() {\n  let a = 1;\n  let a = 2;\n}"),
+            "line 3: Variable a is declared twice in one block"
+        );
+        assert_eq!(
+            error("fn f(a) {\n  let a = 2;\n}\nfn main() {}"),
+            "line 2: Variable a is declared twice in one block"
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_lines() {
+        let error = |source| compile(source).unwrap_err().to_string();
+
+        assert_eq!(
+            error("fn main() {\n  print(x);\n}"),
+            "line 2: Unknown variable x"
+        );
+        assert_eq!(
+            error("fn main() {\n  f(1);\n}\nfn f(a, b) {}"),
+            "line 2: Function f expects 2 arguments but got 1"
+        );
+        assert_eq!(
+            error("fn main() {\n  let = 3;\n}"),
+            "line 2: Expected a name but found '='"
+        );
+        assert_eq!(error("fn f() {}"), "line 1: Missing main function");
+        assert_eq!(
+            error("fn main() { 1 = 2; }"),
+            "line 1: Cannot assign to this"
+        );
+        assert_eq!(
+            error("fn main() { print(1) }"),
+            "line 1: Expected ';' but found '}'"
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use {
+    crate::{
+        memory_patch::parse_change,
+        opcode_computer::{MemorySnapshot, OpcodeComputer, StopReason},
+        partial_evaluator::partially_evaluate,
+    },
+    std::fmt,
+};
+
+// Programs that need longer to reach their first input are run without precomputation
+const PARTIAL_EVALUATION_STEPS: usize = 1_000_000;
+
+#[derive(Debug, PartialEq)]
+pub struct TestCase {
+    pub name: String,
+    pub program: Vec<isize>,
+    pub input: Vec<isize>,
+    pub output: Vec<isize>,
+    pub memory: Vec<(isize, isize)>,
+    pub stop: StopReason,
+}
+
+impl TestCase {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            program: vec![],
+            input: vec![],
+            output: vec![],
+            memory: vec![],
+            stop: StopReason::Halted,
+        }
+    }
+
+    pub fn parse_all(text: &str) -> Vec<Self> {
+        let mut cases: Vec<Self> = vec![];
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                cases.push(Self::new(line[1..line.len() - 1].trim()));
+                continue;
+            }
+
+            let case = cases
+                .last_mut()
+                .unwrap_or_else(|| panic!("Line outside of a test case: {}", line));
+            let parts: Vec<&str> = line.splitn(2, ':').map(str::trim).collect();
+            match parts.as_slice() {
+                ["program", value] => case.program = parse_values(value),
+                ["input", value] => case.input = parse_values(value),
+                ["output", value] => case.output = parse_values(value),
+                ["memory", value] => {
+                    case.memory = value.split(',').map(str::trim).map(parse_change).collect()
+                }
+                ["stop", "halted"] => case.stop = StopReason::Halted,
+                ["stop", "waiting for input"] => case.stop = StopReason::WaitingForInput,
+                _ => panic!("Cannot parse test case line: {}", line),
+            }
+        }
+
+        cases
+    }
+
+    fn check(&self, outcome: &Outcome) -> Vec<String> {
+        let mut problems = vec![];
+
+        if outcome.output != self.output {
+            problems.push(format!(
+                "expected output {:?}, got {:?}",
+                self.output, outcome.output
+            ));
+        }
+        for (address, value) in self.memory.iter() {
+            let actual = outcome.memory.get(*address as usize);
+            if actual != *value {
+                problems.push(format!("expected {} at {}, got {}", value, address, actual));
+            }
+        }
+        if outcome.stop != self.stop {
+            problems.push(format!(
+                "expected to stop as {:?}, got {:?}",
+                self.stop, outcome.stop
+            ));
+        }
+
+        problems
+    }
+}
+
+fn parse_values(line: &str) -> Vec<isize> {
+    line.split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("Cannot parse value: {}", value))
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct Outcome {
+    pub output: Vec<isize>,
+    pub memory: MemorySnapshot,
+    pub stop: StopReason,
+}
+
+pub trait Engine {
+    fn name(&self) -> &str;
+
+    fn execute(&self, program: &[isize], input: &[isize]) -> Outcome;
+}
+
+pub struct ReferenceEngine;
+
+impl Engine for ReferenceEngine {
+    fn name(&self) -> &str {
+        "reference"
+    }
+
+    fn execute(&self, program: &[isize], input: &[isize]) -> Outcome {
+        outcome(&mut started(program, input, 0))
+    }
+}
+
+pub struct PartiallyEvaluatedEngine;
+
+impl Engine for PartiallyEvaluatedEngine {
+    fn name(&self) -> &str {
+        "partially evaluated"
+    }
+
+    fn execute(&self, program: &[isize], input: &[isize]) -> Outcome {
+        let mut computer = match partially_evaluate(program, PARTIAL_EVALUATION_STEPS) {
+            Ok(evaluation) => evaluation.computer(),
+            Err(_) => OpcodeComputer::new(&program.to_vec()),
+        };
+        for value in input {
+            computer.add_input(value);
+        }
+
+        outcome(&mut computer)
+    }
+}
+
+// Runs the first steps, forks, finishes the original and reports the outcome of the fork
+pub struct ForkedEngine {
+    pub after_steps: usize,
+}
+
+impl Engine for ForkedEngine {
+    fn name(&self) -> &str {
+        "forked"
+    }
+
+    fn execute(&self, program: &[isize], input: &[isize]) -> Outcome {
+        let mut computer = started(program, input, self.after_steps);
+        let mut fork = computer.fork();
+        computer.run();
+
+        outcome(&mut fork)
+    }
+}
+
+// Runs the first steps, saves a checkpoint, keeps running and then rewinds to the checkpoint
+pub struct SnapshotRestoredEngine {
+    pub after_steps: usize,
+}
+
+impl Engine for SnapshotRestoredEngine {
+    fn name(&self) -> &str {
+        "snapshot restored"
+    }
+
+    fn execute(&self, program: &[isize], input: &[isize]) -> Outcome {
+        let mut computer = started(program, input, self.after_steps);
+        let checkpoint = computer.checkpoint();
+        computer.run();
+        computer.restore(&checkpoint);
+
+        outcome(&mut computer)
+    }
+}
+
+fn started(program: &[isize], input: &[isize], steps: usize) -> OpcodeComputer {
+    let mut computer = OpcodeComputer::new(&program.to_vec());
+    for value in input {
+        computer.add_input(value);
+    }
+    for _ in 0..steps {
+        if computer.step() != StopReason::Stepped {
+            break;
+        }
+    }
+    computer
+}
+
+fn outcome(computer: &mut OpcodeComputer) -> Outcome {
+    let stop = computer.run();
+
+    Outcome {
+        output: computer.get_all_output(),
+        memory: computer.snapshot(),
+        stop,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Failure {
+    pub engine: String,
+    pub case: String,
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    pub passed: usize,
+    pub failures: Vec<Failure>,
+}
+
+impl Report {
+    pub fn succeeded(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for failure in self.failures.iter() {
+            writeln!(f, "FAILED [{}] {}", failure.engine, failure.case)?;
+            for problem in failure.problems.iter() {
+                writeln!(f, "    {}", problem)?;
+            }
+        }
+        write!(f, "{} passed, {} failed", self.passed, self.failures.len())
+    }
+}
+
+pub fn run_suite(cases: &[TestCase], engines: &[&dyn Engine]) -> Report {
+    let mut report = Report::default();
+
+    for engine in engines {
+        for case in cases {
+            let problems = case.check(&engine.execute(&case.program, &case.input));
+            if problems.is_empty() {
+                report.passed += 1;
+            } else {
+                report.failures.push(Failure {
+                    engine: engine.name().into(),
+                    case: case.name.clone(),
+                    problems,
+                });
+            }
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::fs};
+
+    #[test]
+    fn parses_test_case() {
+        let text = "
+            # Comment
+            [echo]
+            program: 3,0,4,0,99
+            input: 7
+            output: 7
+            memory: 0=7, 4=99
+            stop: waiting for input
+        ";
+
+        let expected = TestCase {
+            name: "echo".into(),
+            program: vec![3, 0, 4, 0, 99],
+            input: vec![7],
+            output: vec![7],
+            memory: vec![(0, 7), (4, 99)],
+            stop: StopReason::WaitingForInput,
+        };
+        assert_eq!(TestCase::parse_all(text), vec![expected]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot parse test case line: stop: crashed")]
+    fn fails_on_unknown_stop_reason() {
+        TestCase::parse_all("[broken]\nstop: crashed");
+    }
+
+    #[test]
+    fn reports_failed_expectations() {
+        let cases = TestCase::parse_all("[wrong]\nprogram: 104,1,99\noutput: 2\nmemory: 0=5");
+        let report = run_suite(&cases, &[&ReferenceEngine]);
+
+        let expected = Failure {
+            engine: "reference".into(),
+            case: "wrong".into(),
+            problems: vec![
+                "expected output [2], got [1]".into(),
+                "expected 5 at 0, got 104".into(),
+            ],
+        };
+        assert_eq!(report.failures, vec![expected]);
+        assert_eq!(
+            report.to_string(),
+            "FAILED [reference] wrong\n    expected output [2], got [1]\n    expected 5 at 0, got 104\n0 passed, 1 failed"
+        );
+    }
+
+    #[test]
+    fn passes_published_examples() {
+        let text = fs::read_to_string("inputs/intcode-conformance.txt").unwrap();
+        let cases = TestCase::parse_all(&text);
+        let engines: [&dyn Engine; 4] = [
+            &ReferenceEngine,
+            &PartiallyEvaluatedEngine,
+            &ForkedEngine { after_steps: 3 },
+            &SnapshotRestoredEngine { after_steps: 3 },
+        ];
+        let report = run_suite(&cases, &engines);
+
+        assert!(report.succeeded(), "{}", report);
+        assert_eq!(report.passed, cases.len() * engines.len());
+    }
+}
@@ -0,0 +1,141 @@
+use crate::opcode_computer::{mnemonic, MemoryError, OpcodeComputer};
+
+#[derive(Debug, PartialEq)]
+pub struct Disassembled {
+    pub address: usize,
+    pub length: usize,
+    pub text: String,
+}
+
+// Fails when the address policy makes the cell unreadable. An instruction whose
+// operands run past the readable memory is shown as data.
+pub fn disassemble(computer: &OpcodeComputer, address: usize) -> Result<Disassembled, MemoryError> {
+    let code = computer.peek(address as isize)?;
+    let (mnemonic, params) = match mnemonic(code % 100) {
+        Some(found) => found,
+        None => return Ok(data(address, code)),
+    };
+    if code < 0 || code / 10_isize.pow(params as u32 + 2) != 0 {
+        return Ok(data(address, code));
+    }
+
+    let mut operands = vec![];
+    for position in 0..params {
+        let mode = (code / 10_isize.pow(position as u32 + 2)) % 10;
+        let value = match computer.peek((address + position + 1) as isize) {
+            Ok(value) => value,
+            Err(_) => return Ok(data(address, code)),
+        };
+        operands.push(match mode {
+            0 => format!("[{}]", value),
+            1 => format!("{}", value),
+            2 if value < 0 => format!("[rb{}]", value),
+            2 => format!("[rb+{}]", value),
+            _ => return Ok(data(address, code)),
+        });
+    }
+
+    let text = if operands.is_empty() {
+        mnemonic.into()
+    } else {
+        format!("{} {}", mnemonic, operands.join(", "))
+    };
+
+    Ok(Disassembled {
+        address,
+        length: params + 1,
+        text,
+    })
+}
+
+// Stops early at the first unreadable cell
+pub fn disassemble_from(
+    computer: &OpcodeComputer,
+    address: usize,
+    count: usize,
+) -> Vec<Disassembled> {
+    let mut result: Vec<Disassembled> = vec![];
+    let mut address = address;
+
+    for _ in 0..count {
+        let instruction = match disassemble(computer, address) {
+            Ok(instruction) => instruction,
+            Err(_) => break,
+        };
+        address += instruction.length;
+        result.push(instruction);
+    }
+
+    result
+}
+
+fn data(address: usize, value: isize) -> Disassembled {
+    Disassembled {
+        address,
+        length: 1,
+        text: format!("DATA {}", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::opcode_computer::AddressPolicy};
+
+    fn texts(program: Vec<isize>, count: usize) -> Vec<String> {
+        let computer = OpcodeComputer::new(&program);
+        disassemble_from(&computer, 0, count)
+            .into_iter()
+            .map(|instruction| instruction.text)
+            .collect()
+    }
+
+    #[test]
+    fn disassembles_parameter_modes() {
+        let program = vec![1001, 5, 3, 0, 21107, -1, 8, -2, 204, 7, 99];
+
+        assert_eq!(
+            texts(program, 4),
+            vec!["ADD [5], 3, [0]", "LT -1, 8, [rb-2]", "OUT [rb+7]", "HLT"]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_data_for_unknown_codes() {
+        let program = vec![42, 30001, 0, 0, 0, 104, 1];
+
+        assert_eq!(
+            texts(program, 4),
+            vec!["DATA 42", "DATA 30001", "DATA 0", "DATA 0"]
+        );
+    }
+
+    #[test]
+    fn stops_at_unreadable_memory() {
+        let mut computer = OpcodeComputer::new(&vec![104, 5, 1101, 1, 2, 0, 99]);
+        computer.set_address_policy(AddressPolicy {
+            max_address: 4,
+            ..AddressPolicy::default()
+        });
+        let texts: Vec<String> = disassemble_from(&computer, 0, 6)
+            .into_iter()
+            .map(|instruction| instruction.text)
+            .collect();
+
+        assert_eq!(texts, vec!["OUT 5", "DATA 1101", "DATA 1", "DATA 2"]);
+        assert_eq!(
+            disassemble(&computer, 5),
+            Err(MemoryError::AddressTooHigh(5))
+        );
+    }
+
+    #[test]
+    fn reports_instruction_lengths() {
+        let computer = OpcodeComputer::new(&vec![3, 0, 1105, 1, 0, 99]);
+        let lengths: Vec<usize> = disassemble_from(&computer, 0, 3)
+            .iter()
+            .map(|instruction| instruction.length)
+            .collect();
+
+        assert_eq!(lengths, vec![2, 3, 1]);
+    }
+}
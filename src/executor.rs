@@ -0,0 +1,161 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+#[derive(Default)]
+pub struct LocalExecutor {
+    tasks: Vec<Task>,
+}
+
+impl LocalExecutor {
+    pub fn spawn<F>(&mut self, future: F) -> &mut Self
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        self.tasks.push(Task::new(Box::pin(future)));
+        self
+    }
+
+    pub fn run(&mut self) {
+        while !self.tasks.is_empty() {
+            let mut progressed = false;
+
+            for task in self.tasks.iter_mut() {
+                if task.woken() {
+                    progressed = true;
+                    task.poll();
+                }
+            }
+
+            self.tasks.retain(|task| !task.done);
+            if !progressed {
+                panic!("All {} tasks are blocked", self.tasks.len());
+            }
+        }
+    }
+}
+
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let flag = Arc::new(WakeFlag(AtomicBool::new(true)));
+    let waker = Waker::from(flag.clone());
+    let mut context = Context::from_waker(&waker);
+
+    loop {
+        if !flag.take() {
+            panic!("Future is blocked and nothing can wake it up");
+        }
+        if let Poll::Ready(result) = future.as_mut().poll(&mut context) {
+            return result;
+        }
+    }
+}
+
+struct Task {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    flag: Arc<WakeFlag>,
+    done: bool,
+}
+
+impl Task {
+    fn new(future: Pin<Box<dyn Future<Output = ()>>>) -> Self {
+        Self {
+            future,
+            flag: Arc::new(WakeFlag(AtomicBool::new(true))),
+            done: false,
+        }
+    }
+
+    fn woken(&self) -> bool {
+        !self.done && self.flag.take()
+    }
+
+    fn poll(&mut self) {
+        let waker = Waker::from(self.flag.clone());
+        let mut context = Context::from_waker(&waker);
+        self.done = self.future.as_mut().poll(&mut context).is_ready();
+    }
+}
+
+struct WakeFlag(AtomicBool);
+
+impl WakeFlag {
+    fn take(&self) -> bool {
+        self.0.swap(false, Ordering::SeqCst)
+    }
+}
+
+impl Wake for WakeFlag {
+    fn wake(self: Arc<Self>) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn blocks_on_ready_future() {
+        assert_eq!(block_on(async { 42 }), 42);
+    }
+
+    #[test]
+    fn interleaves_spawned_tasks() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let mut executor = LocalExecutor::default();
+
+        for name in &["a", "b"] {
+            let log = log.clone();
+            executor.spawn(async move {
+                log.borrow_mut().push(format!("{} started", name));
+                YieldOnce(false).await;
+                log.borrow_mut().push(format!("{} finished", name));
+            });
+        }
+        executor.run();
+
+        assert_eq!(
+            *log.borrow(),
+            vec!["a started", "b started", "a finished", "b finished"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "All 1 tasks are blocked")]
+    fn detects_blocked_tasks() {
+        struct Never;
+
+        impl Future for Never {
+            type Output = ();
+
+            fn poll(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<()> {
+                Poll::Pending
+            }
+        }
+
+        LocalExecutor::default().spawn(Never).run();
+    }
+}
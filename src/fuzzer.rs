@@ -0,0 +1,681 @@
+use {
+    crate::conformance::{Engine, Outcome, ReferenceEngine},
+    std::fmt,
+};
+
+const DATA_CELLS: usize = 16;
+const MAX_UNITS: usize = 12;
+const MAX_LOOP_UNITS: usize = 4;
+const MAX_DEPTH: usize = 2;
+const MAX_ITERATIONS: isize = 4;
+const MAX_INPUT: usize = 8;
+const CODE_READS: usize = 64;
+const BEYOND_CELLS: usize = 8;
+
+// Every executed ADD or MUL at most triples the largest value in memory. Limiting
+// how many of them can run keeps all values far away from overflowing.
+const GROWTH_BUDGET: isize = 24;
+
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift never leaves zero
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    pub fn between(&mut self, low: isize, high: isize) -> isize {
+        low + self.below((high - low + 1) as usize) as isize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operand {
+    Immediate(isize),
+    Data(usize),
+    Code(usize),
+    Relative(usize),
+    // Cells after the loop counters, which are not part of the image
+    Beyond(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operation {
+    pub opcode: isize,
+    pub reads: Vec<Operand>,
+    pub write: Option<Operand>,
+}
+
+impl Operation {
+    fn len(&self) -> usize {
+        1 + self.reads.len() + self.write.iter().count()
+    }
+}
+
+// Programs are built from units, so jumps can only land on unit boundaries and
+// backward jumps only close loops with a dedicated counter.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Unit {
+    Operation(Operation),
+    Shifted(isize, Operation),
+    Skip {
+        jump_if_true: bool,
+        condition: Operand,
+        over: usize,
+    },
+    Loop {
+        iterations: isize,
+        body: Vec<Unit>,
+    },
+}
+
+impl Unit {
+    fn len(&self) -> usize {
+        match self {
+            Unit::Operation(operation) => operation.len(),
+            Unit::Shifted(_, operation) => operation.len() + 4,
+            Unit::Skip { .. } => 3,
+            Unit::Loop { body, .. } => body.iter().map(Unit::len).sum::<usize>() + 11,
+        }
+    }
+
+    fn loops(&self) -> usize {
+        match self {
+            Unit::Loop { body, .. } => 1 + body.iter().map(Unit::loops).sum::<usize>(),
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FuzzProgram {
+    pub units: Vec<Unit>,
+    pub data: Vec<isize>,
+    pub input: Vec<isize>,
+}
+
+impl FuzzProgram {
+    pub fn generate(rng: &mut Rng) -> Self {
+        let mut generator = Generator {
+            rng,
+            growth: GROWTH_BUDGET,
+        };
+        let count = generator.rng.below(MAX_UNITS) + 1;
+        let units = generator.block(count, 0, 1);
+        let data = (0..DATA_CELLS)
+            .map(|_| generator.rng.between(-100, 100))
+            .collect();
+        let input_length = generator.rng.below(MAX_INPUT + 1);
+        let input = (0..input_length)
+            .map(|_| generator.rng.between(-100, 100))
+            .collect();
+
+        Self { units, data, input }
+    }
+
+    // Layout: relative base setup, units, halt, data cells, loop counters
+    pub fn image(&self) -> Vec<isize> {
+        let code_length = 3 + self.units.iter().map(Unit::len).sum::<usize>();
+        let loops = self.units.iter().map(Unit::loops).sum::<usize>();
+
+        let mut assembler = Assembler {
+            code: vec![109, code_length as isize],
+            code_length,
+            data_start: code_length,
+            counters_start: code_length + self.data.len(),
+            counters_used: 0,
+            image_length: code_length + self.data.len() + loops,
+        };
+        assembler.block(&self.units);
+        assembler.code.push(99);
+        assembler.code.extend(&self.data);
+        assembler.code.extend(vec![0; loops]);
+        assembler.code
+    }
+
+    fn simplifications(&self) -> Vec<Self> {
+        let mut candidates = vec![];
+
+        for index in 0..self.input.len() {
+            let mut candidate = self.clone();
+            candidate.input.remove(index);
+            candidates.push(candidate);
+        }
+        for units in simplify_units(&self.units) {
+            candidates.push(Self {
+                units,
+                ..self.clone()
+            });
+        }
+        for (index, value) in self.data.iter().enumerate() {
+            if *value != 0 {
+                let mut candidate = self.clone();
+                candidate.data[index] = 0;
+                candidates.push(candidate);
+            }
+        }
+
+        candidates
+    }
+}
+
+struct Generator<'a> {
+    rng: &'a mut Rng,
+    growth: isize,
+}
+
+impl Generator<'_> {
+    fn block(&mut self, count: usize, depth: usize, multiplier: isize) -> Vec<Unit> {
+        (0..count)
+            .map(|_| match self.rng.below(10) {
+                0 if depth < MAX_DEPTH => {
+                    let iterations = self.rng.between(1, MAX_ITERATIONS);
+                    let count = self.rng.below(MAX_LOOP_UNITS) + 1;
+                    Unit::Loop {
+                        iterations,
+                        body: self.block(count, depth + 1, multiplier * iterations),
+                    }
+                }
+                1 => Unit::Skip {
+                    jump_if_true: self.rng.below(2) == 0,
+                    condition: self.read(0),
+                    over: self.rng.below(3) + 1,
+                },
+                2 => {
+                    let shift = self.rng.between(1, DATA_CELLS as isize / 2);
+                    Unit::Shifted(shift, self.operation(multiplier, shift as usize))
+                }
+                _ => Unit::Operation(self.operation(multiplier, 0)),
+            })
+            .collect()
+    }
+
+    fn operation(&mut self, multiplier: isize, shift: usize) -> Operation {
+        let mut opcode = [1, 2, 7, 8, 3, 4][self.rng.below(6)];
+        if opcode <= 2 {
+            if self.growth < multiplier {
+                opcode = 7;
+            } else {
+                self.growth -= multiplier;
+            }
+        }
+
+        let reads = match opcode {
+            2 => vec![
+                self.read(shift),
+                Operand::Immediate(self.rng.between(-3, 3)),
+            ],
+            3 => vec![],
+            4 => vec![self.read(shift)],
+            _ => vec![self.read(shift), self.read(shift)],
+        };
+        let write = match opcode {
+            4 => None,
+            _ => Some(self.write(shift)),
+        };
+
+        Operation {
+            opcode,
+            reads,
+            write,
+        }
+    }
+
+    fn read(&mut self, shift: usize) -> Operand {
+        match self.rng.below(5) {
+            0 => Operand::Immediate(self.rng.between(-100, 100)),
+            1 => Operand::Code(self.rng.below(CODE_READS)),
+            2 => Operand::Beyond(self.rng.below(BEYOND_CELLS)),
+            _ => self.write(shift),
+        }
+    }
+
+    fn write(&mut self, shift: usize) -> Operand {
+        match self.rng.below(5) {
+            0 => Operand::Beyond(self.rng.below(BEYOND_CELLS)),
+            1 | 2 => Operand::Data(self.rng.below(DATA_CELLS)),
+            _ => Operand::Relative(self.rng.below(DATA_CELLS - shift)),
+        }
+    }
+}
+
+struct Assembler {
+    code: Vec<isize>,
+    code_length: usize,
+    data_start: usize,
+    counters_start: usize,
+    counters_used: usize,
+    image_length: usize,
+}
+
+impl Assembler {
+    fn block(&mut self, units: &[Unit]) {
+        let mut ends = vec![];
+        let mut position = self.code.len();
+        for unit in units {
+            position += unit.len();
+            ends.push(position);
+        }
+
+        for (index, unit) in units.iter().enumerate() {
+            match unit {
+                Unit::Operation(operation) => self.operation(operation),
+                Unit::Shifted(shift, operation) => {
+                    self.code.extend(&[109, *shift]);
+                    self.operation(operation);
+                    self.code.extend(&[109, -shift]);
+                }
+                Unit::Skip {
+                    jump_if_true,
+                    condition,
+                    over,
+                } => {
+                    let opcode = if *jump_if_true { 5 } else { 6 };
+                    let (mode, value) = self.operand(condition);
+                    let target = ends[usize::min(index + over, units.len() - 1)];
+                    self.code
+                        .extend(&[opcode + mode * 100 + 1000, value, target as isize]);
+                }
+                Unit::Loop { iterations, body } => {
+                    let counter = (self.counters_start + self.counters_used) as isize;
+                    self.counters_used += 1;
+                    self.code.extend(&[1101, *iterations, 0, counter]);
+                    let start = self.code.len() as isize;
+                    self.block(body);
+                    self.code.extend(&[1001, counter, -1, counter]);
+                    self.code.extend(&[1005, counter, start]);
+                }
+            }
+        }
+    }
+
+    fn operation(&mut self, operation: &Operation) {
+        let operands: Vec<(isize, isize)> = operation
+            .reads
+            .iter()
+            .chain(operation.write.iter())
+            .map(|operand| self.operand(operand))
+            .collect();
+        let modes: isize = operands
+            .iter()
+            .enumerate()
+            .map(|(position, (mode, _))| mode * 10_isize.pow(position as u32 + 2))
+            .sum();
+
+        self.code.push(operation.opcode + modes);
+        self.code.extend(operands.iter().map(|(_, value)| value));
+    }
+
+    fn operand(&self, operand: &Operand) -> (isize, isize) {
+        match operand {
+            Operand::Immediate(value) => (1, *value),
+            Operand::Data(index) => (0, (self.data_start + index) as isize),
+            Operand::Code(index) => (0, (index % self.code_length) as isize),
+            Operand::Relative(offset) => (2, *offset as isize),
+            Operand::Beyond(index) => (0, (self.image_length + index) as isize),
+        }
+    }
+}
+
+fn simplify_units(units: &[Unit]) -> Vec<Vec<Unit>> {
+    let mut variants = vec![];
+
+    for (index, unit) in units.iter().enumerate() {
+        let replace = |replacement: Vec<Unit>| {
+            let mut variant = units[..index].to_vec();
+            variant.extend(replacement);
+            variant.extend_from_slice(&units[index + 1..]);
+            variant
+        };
+
+        variants.push(replace(vec![]));
+        for simpler in simplify_unit(unit) {
+            variants.push(replace(simpler));
+        }
+    }
+
+    variants
+}
+
+fn simplify_unit(unit: &Unit) -> Vec<Vec<Unit>> {
+    match unit {
+        Unit::Operation(operation) => simplify_operation(operation)
+            .into_iter()
+            .map(|simpler| vec![Unit::Operation(simpler)])
+            .collect(),
+        Unit::Shifted(shift, operation) => {
+            let mut variants = vec![vec![Unit::Operation(operation.clone())]];
+            for simpler in simplify_operation(operation) {
+                variants.push(vec![Unit::Shifted(*shift, simpler)]);
+            }
+            variants
+        }
+        Unit::Skip {
+            jump_if_true,
+            condition,
+            over,
+        } => {
+            let mut variants = vec![];
+            if *over > 1 {
+                variants.push(vec![Unit::Skip {
+                    jump_if_true: *jump_if_true,
+                    condition: *condition,
+                    over: over - 1,
+                }]);
+            }
+            if let Some(condition) = simplify_operand(condition) {
+                variants.push(vec![Unit::Skip {
+                    jump_if_true: *jump_if_true,
+                    condition,
+                    over: *over,
+                }]);
+            }
+            variants
+        }
+        Unit::Loop { iterations, body } => {
+            let mut variants = vec![body.clone()];
+            if *iterations > 1 {
+                variants.push(vec![Unit::Loop {
+                    iterations: 1,
+                    body: body.clone(),
+                }]);
+            }
+            for body in simplify_units(body) {
+                variants.push(vec![Unit::Loop {
+                    iterations: *iterations,
+                    body,
+                }]);
+            }
+            variants
+        }
+    }
+}
+
+fn simplify_operation(operation: &Operation) -> Vec<Operation> {
+    (0..operation.reads.len())
+        .filter_map(|index| {
+            let read = simplify_operand(&operation.reads[index])?;
+            let mut simpler = operation.clone();
+            simpler.reads[index] = read;
+            Some(simpler)
+        })
+        .collect()
+}
+
+fn simplify_operand(operand: &Operand) -> Option<Operand> {
+    match operand {
+        Operand::Immediate(0) => None,
+        _ => Some(Operand::Immediate(0)),
+    }
+}
+
+#[derive(Debug)]
+pub struct Mismatch {
+    pub engine: String,
+    pub program: FuzzProgram,
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |values: &[isize]| {
+            values
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        writeln!(f, "MISMATCH [{}]", self.engine)?;
+        for problem in self.problems.iter() {
+            writeln!(f, "    {}", problem)?;
+        }
+        writeln!(f, "program: {}", join(&self.program.image()))?;
+        write!(f, "input: {}", join(&self.program.input))
+    }
+}
+
+pub fn fuzz(seed: u64, runs: usize, engines: &[&dyn Engine]) -> Option<Mismatch> {
+    let mut rng = Rng::new(seed);
+
+    for _ in 0..runs {
+        let program = FuzzProgram::generate(&mut rng);
+        for engine in engines {
+            if !differences(&program, *engine).is_empty() {
+                let program = shrink(program, *engine);
+                return Some(Mismatch {
+                    engine: engine.name().into(),
+                    problems: differences(&program, *engine),
+                    program,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+pub fn shrink(mut program: FuzzProgram, engine: &dyn Engine) -> FuzzProgram {
+    loop {
+        let smaller = program
+            .simplifications()
+            .into_iter()
+            .find(|candidate| !differences(candidate, engine).is_empty());
+        match smaller {
+            Some(candidate) => program = candidate,
+            None => return program,
+        }
+    }
+}
+
+pub fn differences(program: &FuzzProgram, engine: &dyn Engine) -> Vec<String> {
+    let image = program.image();
+    let expected = ReferenceEngine.execute(&image, &program.input);
+    let actual = engine.execute(&image, &program.input);
+
+    compare(&expected, &actual)
+}
+
+fn compare(expected: &Outcome, actual: &Outcome) -> Vec<String> {
+    let mut problems = vec![];
+
+    if actual.output != expected.output {
+        problems.push(format!(
+            "expected output {:?}, got {:?}",
+            expected.output, actual.output
+        ));
+    }
+    if actual.stop != expected.stop {
+        problems.push(format!(
+            "expected to stop as {:?}, got {:?}",
+            expected.stop, actual.stop
+        ));
+    }
+    for change in expected.memory.diff(&actual.memory) {
+        problems.push(format!(
+            "expected {} at {}, got {}",
+            change.before, change.address, change.after
+        ));
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            conformance::{ForkedEngine, PartiallyEvaluatedEngine, SnapshotRestoredEngine},
+            opcode_computer::{OpcodeComputer, StopReason},
+        },
+    };
+
+    struct ReversedOutputEngine;
+
+    impl Engine for ReversedOutputEngine {
+        fn name(&self) -> &str {
+            "reversed output"
+        }
+
+        fn execute(&self, program: &[isize], input: &[isize]) -> Outcome {
+            let mut outcome = ReferenceEngine.execute(program, input);
+            outcome.output.reverse();
+            outcome
+        }
+    }
+
+    // Leaves extra code right after the image, like a jump stub appended to the program
+    struct PaddedImageEngine;
+
+    impl Engine for PaddedImageEngine {
+        fn name(&self) -> &str {
+            "padded image"
+        }
+
+        fn execute(&self, program: &[isize], input: &[isize]) -> Outcome {
+            let mut padded = program.to_vec();
+            padded.extend(&[1105, 1, 0]);
+            ReferenceEngine.execute(&padded, input)
+        }
+    }
+
+    #[test]
+    fn generates_halting_programs() {
+        let mut rng = Rng::new(7);
+
+        for _ in 0..200 {
+            let program = FuzzProgram::generate(&mut rng);
+            let mut computer = OpcodeComputer::new(&program.image());
+            for value in program.input.iter() {
+                computer.add_input(value);
+            }
+
+            let stop = computer.run();
+            assert!(
+                stop == StopReason::Halted || stop == StopReason::WaitingForInput,
+                "{:?}",
+                program
+            );
+        }
+    }
+
+    #[test]
+    fn assembles_loops_and_skips() {
+        let program = FuzzProgram {
+            units: vec![
+                Unit::Loop {
+                    iterations: 3,
+                    body: vec![Unit::Operation(Operation {
+                        opcode: 4,
+                        reads: vec![Operand::Immediate(5)],
+                        write: None,
+                    })],
+                },
+                Unit::Skip {
+                    jump_if_true: true,
+                    condition: Operand::Immediate(1),
+                    over: 1,
+                },
+                Unit::Operation(Operation {
+                    opcode: 4,
+                    reads: vec![Operand::Immediate(6)],
+                    write: None,
+                }),
+            ],
+            data: vec![],
+            input: vec![],
+        };
+
+        assert_eq!(
+            program.image(),
+            vec![
+                109, 21, 1101, 3, 0, 21, 104, 5, 1001, 21, -1, 21, 1005, 21, 6, 1105, 1, 20, 104,
+                6, 99, 0
+            ]
+        );
+        assert_eq!(
+            ReferenceEngine.execute(&program.image(), &[]).output,
+            vec![5, 5, 5]
+        );
+    }
+
+    #[test]
+    fn alternate_engines_agree_with_reference() {
+        let engines: [&dyn Engine; 3] = [
+            &PartiallyEvaluatedEngine,
+            &ForkedEngine { after_steps: 5 },
+            &SnapshotRestoredEngine { after_steps: 5 },
+        ];
+
+        if let Some(mismatch) = fuzz(2019, 300, &engines) {
+            panic!("{}", mismatch);
+        }
+    }
+
+    #[test]
+    fn compares_cells_past_the_image() {
+        let mismatch = fuzz(3, 10, &[&PaddedImageEngine]).unwrap();
+        let length = mismatch.program.image().len();
+
+        assert_eq!(mismatch.program.units, vec![]);
+        assert_eq!(
+            mismatch.problems,
+            vec![
+                format!("expected 0 at {}, got 1105", length),
+                format!("expected 0 at {}, got 1", length + 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_and_writes_cells_past_the_image() {
+        let program = FuzzProgram {
+            units: vec![
+                Unit::Operation(Operation {
+                    opcode: 1,
+                    reads: vec![Operand::Immediate(2), Operand::Beyond(1)],
+                    write: Some(Operand::Beyond(0)),
+                }),
+                Unit::Operation(Operation {
+                    opcode: 4,
+                    reads: vec![Operand::Beyond(0)],
+                    write: None,
+                }),
+            ],
+            data: vec![],
+            input: vec![],
+        };
+
+        assert_eq!(program.image(), vec![109, 9, 101, 2, 10, 9, 4, 9, 99]);
+        assert_eq!(
+            ReferenceEngine.execute(&program.image(), &[]).output,
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn shrinks_failing_program() {
+        let mismatch = fuzz(1, 100, &[&ReversedOutputEngine]).unwrap();
+
+        assert_eq!(mismatch.engine, "reversed output");
+        assert_eq!(mismatch.program.input, vec![]);
+        assert_eq!(mismatch.program.units.len(), 2);
+        assert!(mismatch
+            .program
+            .units
+            .iter()
+            .all(|unit| matches!(unit, Unit::Operation(Operation { opcode: 4, .. }))));
+    }
+}
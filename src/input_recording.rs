@@ -0,0 +1,164 @@
+use {
+    crate::opcode_computer::{Observer, OpcodeComputer, StopReason},
+    std::{fmt, fs, io},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RecordedInput {
+    pub step: usize,
+    pub value: isize,
+}
+
+// Records every input together with the number of instructions executed before it
+// was added, so a session can be fed back into a fresh computer exactly as it was played.
+#[derive(Debug, Default, PartialEq)]
+pub struct Recording {
+    pub inputs: Vec<RecordedInput>,
+}
+
+impl Recording {
+    pub fn parse(text: &str) -> Self {
+        let inputs = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let parts: Vec<&str> = line.split(':').map(str::trim).collect();
+                match parts.as_slice() {
+                    [step, value] => RecordedInput {
+                        step: step
+                            .parse()
+                            .unwrap_or_else(|_| panic!("Cannot parse step: {}", line)),
+                        value: value
+                            .parse()
+                            .unwrap_or_else(|_| panic!("Cannot parse value: {}", line)),
+                    },
+                    _ => panic!("Cannot parse recorded input: {}", line),
+                }
+            })
+            .collect();
+
+        Self { inputs }
+    }
+
+    pub fn load(path: &str) -> Self {
+        Self::parse(&fs::read_to_string(path).unwrap())
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# step: value")?;
+        for input in self.inputs.iter() {
+            writeln!(f, "{}: {}", input.step, input.value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Observer for Recording {
+    fn input_added(&mut self, computer: &OpcodeComputer, value: isize) {
+        self.inputs.push(RecordedInput {
+            step: computer.steps(),
+            value,
+        });
+    }
+}
+
+// The computer has to be in the same state as the recorded one was when recording started
+pub fn replay(computer: &mut OpcodeComputer, recording: &Recording) -> StopReason {
+    for input in recording.inputs.iter() {
+        while computer.steps() < input.step {
+            if computer.step() != StopReason::Stepped {
+                break;
+            }
+        }
+
+        if computer.steps() != input.step {
+            panic!(
+                "Replay diverged: input {} was recorded at step {}, but the computer stopped at step {}",
+                input.value,
+                input.step,
+                computer.steps()
+            );
+        }
+        computer.add_input(&input.value);
+    }
+
+    computer.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{cell::RefCell, rc::Rc},
+    };
+
+    // Outputs the sum of inputs until it reads zero
+    const SUM: [isize; 17] = [
+        3, 15, 1006, 15, 12, 1, 15, 16, 16, 1105, 1, 0, 4, 16, 99, 0, 0,
+    ];
+
+    #[test]
+    fn records_inputs_with_steps() {
+        let recording = Rc::new(RefCell::new(Recording::default()));
+        let mut computer = OpcodeComputer::new(&vec![3, 7, 3, 8, 4, 7, 99, 0, 0]);
+        computer.add_observer(recording.clone()).add_input(&5).run();
+        computer.add_input(&-3).run();
+
+        let expected = vec![
+            RecordedInput { step: 0, value: 5 },
+            RecordedInput { step: 2, value: -3 },
+        ];
+        assert_eq!(recording.borrow().inputs, expected);
+    }
+
+    #[test]
+    fn saves_and_parses_recordings() {
+        let recording = Recording::parse("# step: value\n0: 5\n\n17: -1\n");
+
+        assert_eq!(
+            recording.inputs,
+            vec![
+                RecordedInput { step: 0, value: 5 },
+                RecordedInput {
+                    step: 17,
+                    value: -1
+                },
+            ]
+        );
+        assert_eq!(recording.to_string(), "# step: value\n0: 5\n17: -1\n");
+    }
+
+    #[test]
+    fn replays_interactive_session() {
+        let recording = Rc::new(RefCell::new(Recording::default()));
+        let mut live = OpcodeComputer::new(&SUM.to_vec());
+        live.add_observer(recording.clone());
+        for value in [4, 10, 0].iter() {
+            live.run();
+            live.add_input(value);
+        }
+        live.run();
+
+        let mut replayed = OpcodeComputer::new(&SUM.to_vec());
+        assert_eq!(
+            replay(&mut replayed, &recording.borrow()),
+            StopReason::Halted
+        );
+        assert_eq!(replayed.get_all_output(), live.get_all_output());
+        assert_eq!(replayed.steps(), live.steps());
+    }
+
+    #[test]
+    #[should_panic(expected = "Replay diverged: input 1 was recorded at step 50")]
+    fn fails_when_replay_diverges() {
+        let recording = Recording::parse("0: 4\n50: 1");
+        replay(&mut OpcodeComputer::new(&SUM.to_vec()), &recording);
+    }
+}
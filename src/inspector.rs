@@ -0,0 +1,336 @@
+use {
+    crate::{
+        disassembler::disassemble_from,
+        opcode_computer::{ComputerState, MemorySnapshot, OpcodeComputer, StopReason},
+    },
+    pancurses::{
+        cbreak, curs_set, endwin, initscr, noecho, Input, Window, A_BOLD, A_REVERSE, A_UNDERLINE,
+    },
+};
+
+const CELL_WIDTH: usize = 9;
+const ADDRESS_WIDTH: usize = 7;
+const SIDE_PANE_WIDTH: i32 = 42;
+// Programs that loop without output are interrupted after this many steps
+const OUTPUT_STEPS: usize = 1_000_000;
+const HELP: &str =
+    "s step  r run/pause  o until output  i input  x hex  +/- speed  g follow  q quit";
+
+pub fn inspect(computer: OpcodeComputer) {
+    let window = initscr();
+    window.keypad(true);
+    cbreak();
+    noecho();
+    curs_set(0);
+
+    Inspector::new(computer).run(&window);
+
+    endwin();
+}
+
+struct Inspector {
+    computer: OpcodeComputer,
+    snapshot: MemorySnapshot,
+    changed: Vec<usize>,
+    first_row: usize,
+    follow: bool,
+    hex: bool,
+    running: bool,
+    speed: usize,
+    steps: usize,
+    status: String,
+}
+
+impl Inspector {
+    fn new(computer: OpcodeComputer) -> Self {
+        Self {
+            snapshot: computer.snapshot(),
+            computer,
+            changed: vec![],
+            first_row: 0,
+            follow: true,
+            hex: false,
+            running: false,
+            speed: 1,
+            steps: 0,
+            status: "Ready".into(),
+        }
+    }
+
+    fn run(&mut self, window: &Window) {
+        loop {
+            self.draw(window);
+            window.timeout(if self.running { 50 } else { -1 });
+
+            match window.getch() {
+                Some(Input::Character('q')) => break,
+                Some(Input::Character('s')) => {
+                    self.execute(|computer| computer.step());
+                }
+                Some(Input::Character('o')) => self.run_until_output(OUTPUT_STEPS),
+                Some(Input::Character('r')) => self.running = !self.running,
+                Some(Input::Character('i')) => self.read_input(window),
+                Some(Input::Character('x')) => self.hex = !self.hex,
+                Some(Input::Character('g')) => self.follow = !self.follow,
+                Some(Input::Character('+')) => self.speed = (self.speed * 10).min(100_000),
+                Some(Input::Character('-')) => self.speed = (self.speed / 10).max(1),
+                Some(Input::KeyUp) => self.scroll(-1),
+                Some(Input::KeyDown) => self.scroll(1),
+                Some(Input::KeyPPage) => self.scroll(-(self.memory_rows(window) as isize)),
+                Some(Input::KeyNPage) => self.scroll(self.memory_rows(window) as isize),
+                _ => {}
+            }
+
+            if self.running {
+                let speed = self.speed;
+                self.execute(|computer| {
+                    for _ in 0..speed {
+                        match computer.step() {
+                            StopReason::Stepped => continue,
+                            reason => return reason,
+                        }
+                    }
+                    StopReason::Stepped
+                });
+            }
+        }
+    }
+
+    fn run_until_output(&mut self, max_steps: usize) {
+        let reason = self.execute(|computer| {
+            let expected = computer.pending_output().len() + 1;
+            for _ in 0..max_steps {
+                match computer.step() {
+                    StopReason::Stepped if computer.pending_output().len() >= expected => {
+                        return StopReason::OutputProduced
+                    }
+                    StopReason::Stepped => continue,
+                    reason => return reason,
+                }
+            }
+            StopReason::Stepped
+        });
+
+        if reason == StopReason::Stepped {
+            self.status = format!("No output within {} steps", max_steps);
+        }
+    }
+
+    fn execute<F>(&mut self, action: F) -> StopReason
+    where
+        F: FnOnce(&mut OpcodeComputer) -> StopReason,
+    {
+        let reason = action(&mut self.computer);
+        let snapshot = self.computer.snapshot();
+
+        self.changed = self
+            .snapshot
+            .diff(&snapshot)
+            .iter()
+            .map(|change| change.address)
+            .collect();
+        self.snapshot = snapshot;
+        self.steps += 1;
+        self.status = format!("{:?}", reason);
+        if reason != StopReason::Stepped {
+            self.running = false;
+        }
+        reason
+    }
+
+    fn read_input(&mut self, window: &Window) {
+        let mut text = String::new();
+        window.timeout(-1);
+
+        loop {
+            self.status = format!("Input: {}_", text);
+            self.draw(window);
+
+            match window.getch() {
+                Some(Input::Character('\n')) => break,
+                Some(Input::Character('\u{1b}')) => {
+                    self.status = "Input cancelled".into();
+                    return;
+                }
+                Some(Input::KeyBackspace) | Some(Input::Character('\u{7f}')) => {
+                    text.pop();
+                }
+                Some(Input::Character(c)) if c.is_ascii_digit() || c == '-' => text.push(c),
+                _ => {}
+            }
+        }
+
+        self.status = match text.parse() {
+            Ok(value) => {
+                self.computer.add_input(&value);
+                format!("Added input {}", value)
+            }
+            Err(_) => format!("Not a number: {}", text),
+        };
+    }
+
+    fn scroll(&mut self, rows: isize) {
+        self.follow = false;
+        self.first_row = (self.first_row as isize + rows).max(0) as usize;
+    }
+
+    fn memory_width(window: &Window) -> i32 {
+        window.get_max_x() - SIDE_PANE_WIDTH
+    }
+
+    fn cells_per_row(window: &Window) -> usize {
+        ((Self::memory_width(window) as usize).saturating_sub(ADDRESS_WIDTH) / CELL_WIDTH).max(1)
+    }
+
+    fn memory_rows(&self, window: &Window) -> usize {
+        (window.get_max_y() - 2).max(1) as usize
+    }
+
+    fn draw(&mut self, window: &Window) {
+        window.erase();
+        self.draw_memory(window);
+        self.draw_side_pane(window);
+
+        let bottom = window.get_max_y() - 1;
+        window.mvaddnstr(bottom - 1, 0, &self.status, window.get_max_x());
+        window.attron(A_REVERSE);
+        window.mvaddnstr(bottom, 0, HELP, window.get_max_x());
+        window.attroff(A_REVERSE);
+        window.refresh();
+    }
+
+    fn draw_memory(&mut self, window: &Window) {
+        let per_row = Self::cells_per_row(window);
+        let rows = self.memory_rows(window);
+        let ip = self.computer.instruction_pointer();
+        let rb = self.computer.relative_base();
+
+        if self.follow {
+            let ip_row = ip / per_row;
+            if ip_row < self.first_row || ip_row >= self.first_row + rows {
+                self.first_row = ip_row.saturating_sub(rows / 2);
+            }
+        }
+
+        for row in 0..rows {
+            let y = row as i32;
+            let first_address = (self.first_row + row) * per_row;
+            window.mvaddstr(y, 0, format!("{:>6}:", first_address));
+
+            for column in 0..per_row {
+                let address = first_address + column;
+                let attributes = if address == ip {
+                    A_REVERSE
+                } else if self.changed.contains(&address) {
+                    A_BOLD
+                } else if address as isize == rb {
+                    A_UNDERLINE
+                } else {
+                    0
+                };
+
+                window.attron(attributes);
+                window.mvaddstr(
+                    y,
+                    (ADDRESS_WIDTH + column * CELL_WIDTH) as i32,
+                    format_cell(self.snapshot.get(address), self.hex),
+                );
+                window.attroff(attributes);
+            }
+        }
+    }
+
+    fn draw_side_pane(&self, window: &Window) {
+        let x = Self::memory_width(window) + 1;
+        let width = SIDE_PANE_WIDTH - 1;
+        let height = window.get_max_y() - 2;
+        let mut lines = vec![
+            format!("ip: {}", self.computer.instruction_pointer()),
+            format!("rb: {}", self.computer.relative_base()),
+            format!("state: {}", format_state(self.computer.state())),
+            format!("actions: {}  speed: {}", self.steps, self.speed),
+            String::new(),
+        ];
+
+        let queues = 5;
+        let listing = (height as usize).saturating_sub(lines.len() + queues);
+        for instruction in
+            disassemble_from(&self.computer, self.computer.instruction_pointer(), listing)
+        {
+            lines.push(format!("{:>6}  {}", instruction.address, instruction.text));
+        }
+
+        lines.push(String::new());
+        lines.push("input:".into());
+        lines.push(format_queue(self.computer.pending_input()));
+        lines.push("output:".into());
+        lines.push(format_queue(self.computer.pending_output()));
+
+        for (y, line) in lines.iter().enumerate() {
+            window.mvaddnstr(y as i32, x, line, width);
+        }
+        window.mv(0, x - 1);
+        window.vline('|', height);
+    }
+}
+
+fn format_cell(value: isize, hex: bool) -> String {
+    let text = match (hex, value < 0) {
+        (true, true) => format!("-{:x}", value.unsigned_abs()),
+        (true, false) => format!("{:x}", value),
+        (false, _) => format!("{}", value),
+    };
+    let width = CELL_WIDTH - 1;
+
+    if text.len() > width {
+        format!("~{}", &text[text.len() - width + 1..])
+    } else {
+        format!("{:>width$}", text, width = width)
+    }
+}
+
+fn format_state(state: &ComputerState) -> &str {
+    match state {
+        ComputerState::Initialized => "initialized",
+        ComputerState::Running => "running",
+        ComputerState::WaitingForInput(_) => "waiting for input",
+        ComputerState::Halted => "halted",
+        ComputerState::Faulted(_) => "faulted",
+    }
+}
+
+fn format_queue(values: &[isize]) -> String {
+    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    format!("[{}]", values.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_cells() {
+        assert_eq!(format_cell(99, false), "      99");
+        assert_eq!(format_cell(-255, true), "     -ff");
+        assert_eq!(format_cell(1219070632396864, false), "~2396864");
+    }
+
+    #[test]
+    fn formats_queues() {
+        assert_eq!(format_queue(&[]), "[]");
+        assert_eq!(format_queue(&[1, -2]), "[1, -2]");
+    }
+
+    #[test]
+    fn runs_until_output_within_step_budget() {
+        let mut inspector = Inspector::new(OpcodeComputer::new(&vec![1101, 1, 1, 9, 104, 5, 99]));
+        inspector.run_until_output(10);
+        assert_eq!(inspector.status, "OutputProduced");
+        assert_eq!(inspector.computer.pending_output(), &[5]);
+
+        let mut looping = Inspector::new(OpcodeComputer::new(&vec![1105, 1, 0]));
+        looping.run_until_output(100);
+        assert_eq!(looping.status, "No output within 100 steps");
+        assert_eq!(looping.computer.steps(), 100);
+    }
+}
@@ -0,0 +1,38 @@
+pub mod aoc_01;
+pub mod aoc_02;
+pub mod aoc_03;
+pub mod aoc_04;
+pub mod aoc_05;
+pub mod aoc_06;
+pub mod aoc_07;
+pub mod aoc_08;
+pub mod aoc_09;
+pub mod aoc_10;
+pub mod aoc_11;
+pub mod aoc_12;
+pub mod aoc_13;
+pub mod aoc_14;
+pub mod aoc_15;
+pub mod aoc_16;
+pub mod aoc_17;
+pub mod aoc_18;
+pub mod aoc_19;
+pub mod aoc_20;
+pub mod aoc_21;
+pub mod aoc_22;
+pub mod aoc_24;
+pub mod aoc_25;
+pub mod ascii_computer;
+pub mod async_computer;
+pub mod compiler;
+pub mod conformance;
+pub mod disassembler;
+pub mod executor;
+pub mod fuzzer;
+pub mod input_recording;
+pub mod inspector;
+pub mod memory_patch;
+pub mod memory_scan;
+pub mod opcode_computer;
+pub mod partial_evaluator;
+pub mod read_input;
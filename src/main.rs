@@ -1,21 +1,94 @@
-mod aoc_01;
-mod aoc_02;
-mod aoc_03;
-mod aoc_04;
-mod aoc_05;
-mod aoc_06;
-mod aoc_07;
-mod aoc_08;
-mod aoc_09;
-mod aoc_10;
-mod aoc_11;
-mod aoc_12;
-mod aoc_13;
-mod aoc_14;
-mod opcode_computer;
-mod read_input;
+use {
+    aoc_2019_rust::{
+        aoc_01, aoc_02, aoc_03, aoc_04, aoc_05, aoc_06, aoc_07, aoc_08, aoc_09, aoc_10, aoc_11,
+        aoc_12, aoc_13, aoc_14, aoc_15, aoc_16, aoc_17, aoc_18, aoc_19, aoc_20, aoc_21, aoc_22,
+        aoc_24, aoc_25,
+        conformance::{Engine, ForkedEngine, PartiallyEvaluatedEngine, SnapshotRestoredEngine},
+        fuzzer,
+        input_recording::Recording,
+        inspector,
+        opcode_computer::OpcodeComputer,
+        read_input::{read_intcode_program, read_patch},
+    },
+    std::{env, process},
+};
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [command, day, patches @ ..] if command == "inspect" => inspect(day, patches),
+        [command, day, input @ ..] if command == "stats" => stats(day, input),
+        [command, seed, runs] if command == "fuzz" => fuzz(seed, runs),
+        [command, path] if command == "record-13" => aoc_13::record().save(path).unwrap(),
+        [command, path] if command == "autoplay-13" => aoc_13::autoplay().save(path).unwrap(),
+        [command, path] if command == "replay-13" => replay_13(path),
+        [command] if command == "beam-19" => println!("{}", aoc_19::render_beam()),
+        [command] if command == "play-25" => play_25(),
+        [command] if command == "solve-25" => solve_25(),
+        _ => run_all(),
+    }
+}
+
+fn inspect(day: &str, patches: &[String]) {
+    let day = day.parse().expect("Day number is expected");
+    let mut computer = OpcodeComputer::new(&read_intcode_program(day));
+    for name in patches {
+        read_patch(day, name).apply(&mut computer).unwrap();
+    }
+
+    inspector::inspect(computer);
+}
+
+fn stats(day: &str, input: &[String]) {
+    let day = day.parse().expect("Day number is expected");
+    let mut computer = OpcodeComputer::new(&read_intcode_program(day));
+    for value in input {
+        computer.add_input(&value.parse().expect("Input values must be numbers"));
+    }
+
+    println!("stopped: {:?}", computer.run());
+    println!("{}", computer.stats());
+}
+
+fn fuzz(seed: &str, runs: &str) {
+    let seed = seed.parse().expect("Seed must be a number");
+    let runs = runs.parse().expect("Number of runs is expected");
+    let engines: [&dyn Engine; 3] = [
+        &PartiallyEvaluatedEngine,
+        &ForkedEngine { after_steps: 5 },
+        &SnapshotRestoredEngine { after_steps: 5 },
+    ];
+
+    match fuzzer::fuzz(seed, runs, &engines) {
+        Some(mismatch) => println!("{}", mismatch),
+        None => println!("{} programs, no mismatches", runs),
+    }
+}
+
+fn play_25() {
+    if let Err(error) = aoc_25::play() {
+        eprintln!("Cannot play day 25: {}", error);
+        process::exit(1);
+    }
+}
+
+fn solve_25() {
+    match aoc_25::aoc_25_01() {
+        Ok(password) => println!("{}", password),
+        Err(error) => {
+            eprintln!("Cannot solve day 25: {}", error);
+            process::exit(1);
+        }
+    }
+}
+
+fn replay_13(path: &str) {
+    let (score, blocks_left) = aoc_13::replay_session(&Recording::load(path));
+    println!("score: {}, blocks left: {}", score, blocks_left);
+}
+
+fn run_all() {
     println!("01 / 01: {}", aoc_01::aoc_01_01());
     println!("01 / 02: {}", aoc_01::aoc_01_02());
     println!("02 / 01: {}", aoc_02::aoc_02_01());
@@ -45,4 +118,22 @@ fn main() {
     println!("13 / 02: {}", aoc_13::aoc_13_02());
     println!("14 / 01: {}", aoc_14::aoc_14_01());
     println!("14 / 02: {}", aoc_14::aoc_14_02());
+    println!("15 / 01: {}", aoc_15::aoc_15_01());
+    println!("15 / 02: {}", aoc_15::aoc_15_02());
+    println!("16 / 01: {}", aoc_16::aoc_16_01());
+    println!("16 / 02: {}", aoc_16::aoc_16_02());
+    println!("17 / 01: {}", aoc_17::aoc_17_01());
+    println!("17 / 02: {}", aoc_17::aoc_17_02());
+    println!("18 / 01: {}", aoc_18::aoc_18_01());
+    println!("18 / 02: {}", aoc_18::aoc_18_02());
+    println!("19 / 01: {}", aoc_19::aoc_19_01());
+    println!("19 / 02: {}", aoc_19::aoc_19_02());
+    println!("20 / 01: {}", aoc_20::aoc_20_01());
+    println!("20 / 02: {}", aoc_20::aoc_20_02());
+    println!("21 / 01: {}", aoc_21::aoc_21_01());
+    println!("21 / 02: {}", aoc_21::aoc_21_02());
+    println!("22 / 01: {}", aoc_22::aoc_22_01());
+    println!("22 / 02: {}", aoc_22::aoc_22_02());
+    println!("24 / 01: {}", aoc_24::aoc_24_01());
+    println!("24 / 02: {}", aoc_24::aoc_24_02());
 }
@@ -0,0 +1,121 @@
+use crate::opcode_computer::{MemoryError, OpcodeComputer};
+
+#[derive(Debug, PartialEq)]
+pub struct Patch {
+    pub name: String,
+    pub changes: Vec<(isize, isize)>,
+}
+
+impl Patch {
+    pub fn parse_all(text: &str) -> Vec<Self> {
+        let mut patches: Vec<Self> = vec![];
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                patches.push(Self {
+                    name: line[1..line.len() - 1].trim().into(),
+                    changes: vec![],
+                });
+                continue;
+            }
+
+            let patch = patches
+                .last_mut()
+                .unwrap_or_else(|| panic!("Change outside of a named patch: {}", line));
+            patch.changes.push(parse_change(line));
+        }
+
+        patches
+    }
+
+    pub fn apply(&self, computer: &mut OpcodeComputer) -> Result<(), MemoryError> {
+        for (address, value) in self.changes.iter() {
+            computer.poke(*address, *value)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn parse_change(line: &str) -> (isize, isize) {
+    let parts: Vec<&str> = line.split('=').map(str::trim).collect();
+    if parts.len() != 2 {
+        panic!("Cannot parse patch line: {}", line);
+    }
+
+    let address = parts[0]
+        .parse()
+        .unwrap_or_else(|_| panic!("Cannot parse address: {}", line));
+    let value = parts[1]
+        .parse()
+        .unwrap_or_else(|_| panic!("Cannot parse value: {}", line));
+
+    (address, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_named_patches() {
+        let text = "
+            # Comment
+            [1202 program alarm]
+            1=12
+            2 = 2
+
+            [free play]
+            0=-2
+        ";
+
+        let expected = vec![
+            Patch {
+                name: "1202 program alarm".into(),
+                changes: vec![(1, 12), (2, 2)],
+            },
+            Patch {
+                name: "free play".into(),
+                changes: vec![(0, -2)],
+            },
+        ];
+        assert_eq!(Patch::parse_all(text), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Change outside of a named patch: 1=2")]
+    fn fails_on_change_without_name() {
+        Patch::parse_all("1=2");
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot parse patch line: 1=2=3")]
+    fn fails_on_malformed_change() {
+        Patch::parse_all("[broken]\n1=2=3");
+    }
+
+    #[test]
+    fn applies_patch_before_execution() {
+        let patch = &Patch::parse_all("[double]\n1=0\n2=0")[0];
+        let mut computer = OpcodeComputer::new(&vec![1, 5, 6, 0, 99, 7, 8]);
+        patch.apply(&mut computer).unwrap();
+        computer.run();
+
+        assert_eq!(computer.first_cell(), 2);
+    }
+
+    #[test]
+    fn stops_on_invalid_address() {
+        let patch = &Patch::parse_all("[broken]\n-1=0")[0];
+        let mut computer = OpcodeComputer::new(&vec![99]);
+
+        assert_eq!(
+            patch.apply(&mut computer),
+            Err(MemoryError::NegativeAddress(-1))
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use {crate::opcode_computer::MemorySnapshot, std::collections::BTreeMap};
+
+#[derive(Debug, Default)]
+pub struct MemoryScan {
+    candidates: Option<BTreeMap<usize, isize>>,
+}
+
+impl MemoryScan {
+    pub fn observe(&mut self, snapshot: &MemorySnapshot, value: isize) -> &mut Self {
+        let candidates = match self.candidates.take() {
+            None => snapshot
+                .addresses()
+                .map(|address| (address, snapshot.get(address) - value))
+                .collect(),
+            Some(candidates) => candidates
+                .into_iter()
+                .filter(|(address, offset)| snapshot.get(*address) - value == *offset)
+                .collect(),
+        };
+        self.candidates = Some(candidates);
+        self
+    }
+
+    pub fn observe_equal(&mut self, snapshot: &MemorySnapshot, value: isize) -> &mut Self {
+        self.observe(snapshot, value);
+        if let Some(candidates) = self.candidates.as_mut() {
+            candidates.retain(|_, offset| *offset == 0);
+        }
+        self
+    }
+
+    pub fn candidates(&self) -> Vec<usize> {
+        self.candidates
+            .iter()
+            .flat_map(|candidates| candidates.keys().cloned())
+            .collect()
+    }
+
+    pub fn found(&self) -> Option<usize> {
+        match self.candidates().as_slice() {
+            [address] => Some(*address),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::opcode_computer::OpcodeComputer};
+
+    fn counter_program() -> Vec<isize> {
+        // Reads a step, adds it to cell 20 and writes double of cell 20 to cell 21
+        vec![
+            3, 22, 1, 20, 22, 20, 1002, 20, 2, 21, 4, 20, 1105, 1, 0, 0, 0, 0, 0, 0, 7, 0, 0,
+        ]
+    }
+
+    #[test]
+    fn narrows_cells_changing_in_step_with_value() {
+        let mut computer = OpcodeComputer::new(&counter_program());
+        let mut scan = MemoryScan::default();
+        let mut observed = 0;
+
+        computer.run();
+        scan.observe(&computer.snapshot(), observed);
+        for step in &[1, 2, 5] {
+            computer.add_input(step).run();
+            observed += step;
+            scan.observe(&computer.snapshot(), observed);
+        }
+
+        assert_eq!(scan.found(), Some(20));
+    }
+
+    #[test]
+    fn narrows_cells_equal_to_value() {
+        let mut computer = OpcodeComputer::new(&counter_program());
+        let mut scan = MemoryScan::default();
+
+        for step in &[1, 2, 5] {
+            computer.add_input(step).run();
+            scan.observe_equal(&computer.snapshot(), computer.peek(20).unwrap() * 2);
+        }
+
+        assert_eq!(scan.candidates(), vec![21]);
+    }
+}
@@ -1,24 +1,221 @@
-use std::{collections::HashMap, thread::sleep, time::Duration};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap},
+    fmt, mem,
+    rc::Rc,
+    thread::sleep,
+    time::{Duration, Instant},
+};
 
 #[derive(Debug)]
 pub struct OpcodeComputer {
     instructions: Vec<isize>,
     instruction_pointer: usize,
     extended_memory: HashMap<usize, isize>,
-    pub state: ComputerState,
+    state: ComputerState,
     input: Vec<isize>,
     output: Vec<isize>,
     relative_base: isize,
-    debug: bool,
-    name: Option<String>,
+    extensions: HashMap<isize, Extension>,
+    observers: Vec<Box<dyn Observer>>,
+    counters: Counters,
+    address_policy: AddressPolicy,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ComputerState {
     Initialized,
     Running,
     WaitingForInput(Param),
     Halted,
+    Faulted(Fault),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NegativeAddresses {
+    Error,
+    Wrap,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AddressPolicy {
+    pub max_address: usize,
+    pub max_extended_cells: usize,
+    pub negative_addresses: NegativeAddresses,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    instructions: Vec<isize>,
+    extended_memory: HashMap<usize, isize>,
+    instruction_pointer: usize,
+    relative_base: isize,
+    state: ComputerState,
+    input: Vec<isize>,
+    output: Vec<isize>,
+}
+
+impl Default for AddressPolicy {
+    fn default() -> Self {
+        Self {
+            max_address: usize::MAX,
+            max_extended_cells: usize::MAX,
+            negative_addresses: NegativeAddresses::Error,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Counters {
+    opcodes: [usize; 100],
+    inputs: usize,
+    outputs: usize,
+    highest_address: Cell<usize>,
+    min_relative_base: isize,
+    max_relative_base: isize,
+    wall_time: Duration,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self {
+            opcodes: [0; 100],
+            inputs: 0,
+            outputs: 0,
+            highest_address: Cell::new(0),
+            min_relative_base: 0,
+            max_relative_base: 0,
+            wall_time: Duration::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub instructions: BTreeMap<isize, usize>,
+    pub inputs: usize,
+    pub outputs: usize,
+    pub highest_address: usize,
+    pub extended_memory_cells: usize,
+    pub min_relative_base: isize,
+    pub max_relative_base: isize,
+    pub wall_time: Duration,
+}
+
+impl Stats {
+    pub fn total_instructions(&self) -> usize {
+        self.instructions.values().sum()
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "instructions: {}", self.total_instructions())?;
+        for (opcode, count) in self.instructions.iter() {
+            let name = mnemonic(*opcode).map_or("EXT", |(name, _)| name);
+            writeln!(f, "  {:>2} {:<3} {}", opcode, name, count)?;
+        }
+        writeln!(f, "inputs: {}", self.inputs)?;
+        writeln!(f, "outputs: {}", self.outputs)?;
+        writeln!(f, "highest address: {}", self.highest_address)?;
+        writeln!(f, "extended memory cells: {}", self.extended_memory_cells)?;
+        writeln!(
+            f,
+            "relative base: {}..={}",
+            self.min_relative_base, self.max_relative_base
+        )?;
+        write!(f, "wall time: {:?}", self.wall_time)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemorySnapshot {
+    cells: BTreeMap<usize, isize>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CellChange {
+    pub address: usize,
+    pub before: isize,
+    pub after: isize,
+}
+
+impl MemorySnapshot {
+    pub fn new(image: &[isize], extended_memory: &HashMap<usize, isize>) -> Self {
+        let mut cells: BTreeMap<usize, isize> = image.iter().cloned().enumerate().collect();
+        cells.extend(
+            extended_memory
+                .iter()
+                .map(|(address, value)| (*address, *value)),
+        );
+
+        Self { cells }
+    }
+
+    pub fn get(&self, address: usize) -> isize {
+        *self.cells.get(&address).unwrap_or(&0)
+    }
+
+    pub fn addresses(&self) -> impl Iterator<Item = usize> + '_ {
+        self.cells.keys().cloned()
+    }
+
+    pub fn diff(&self, other: &Self) -> Vec<CellChange> {
+        let mut addresses: Vec<usize> = self.addresses().chain(other.addresses()).collect();
+        addresses.sort_unstable();
+        addresses.dedup();
+
+        addresses
+            .into_iter()
+            .map(|address| CellChange {
+                address,
+                before: self.get(address),
+                after: other.get(address),
+            })
+            .filter(|change| change.before != change.after)
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MemoryError {
+    NegativeAddress(isize),
+    AddressTooHigh(usize),
+    ExtendedMemoryExhausted(usize),
+    AddressOverflow,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FaultError {
+    Memory(MemoryError),
+    UnknownOpcode(isize),
+}
+
+impl From<MemoryError> for FaultError {
+    fn from(error: MemoryError) -> Self {
+        FaultError::Memory(error)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fault {
+    pub ip: usize,
+    pub error: FaultError,
+}
+
+// Extensions can only use the free opcodes below 100
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnavailableOpcode {
+    pub opcode: isize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReason {
+    Stepped,
+    OutputProduced,
+    WaitingForInput,
+    Halted,
+    Fault(Fault),
 }
 
 impl OpcodeComputer {
@@ -31,13 +228,52 @@ impl OpcodeComputer {
             input: vec![],
             output: vec![],
             relative_base: 0,
-            debug: false,
-            name: None,
+            extensions: HashMap::new(),
+            observers: vec![],
+            counters: Counters::default(),
+            address_policy: AddressPolicy::default(),
+        }
+    }
+
+    pub fn set_address_policy(&mut self, policy: AddressPolicy) -> &mut Self {
+        self.address_policy = policy;
+        self
+    }
+
+    pub fn register_extension<F>(
+        &mut self,
+        opcode: isize,
+        params: usize,
+        callback: F,
+    ) -> Result<&mut Self, UnavailableOpcode>
+    where
+        F: FnMut(&mut ExtensionCall) -> Result<(), MemoryError> + 'static,
+    {
+        if !(0..100).contains(&opcode) || Instruction::is_builtin(opcode) {
+            return Err(UnavailableOpcode { opcode });
         }
+
+        self.extensions.insert(
+            opcode,
+            Extension {
+                params,
+                callback: Box::new(callback),
+            },
+        );
+        Ok(self)
+    }
+
+    pub fn add_observer<O>(&mut self, observer: O) -> &mut Self
+    where
+        O: Observer + 'static,
+    {
+        self.observers.push(Box::new(observer));
+        self
     }
 
     pub fn add_input(&mut self, input: &isize) -> &mut Self {
         self.input.push(input.clone());
+        self.notify(|observer, computer| observer.input_added(computer, *input));
         self
     }
 
@@ -59,161 +295,530 @@ impl OpcodeComputer {
         self.state == ComputerState::Halted
     }
 
-    pub fn run(&mut self) -> isize {
-        use ComputerState::*;
+    // Neither a halted nor a faulted computer runs any further
+    pub fn finished(&self) -> bool {
+        matches!(
+            self.state,
+            ComputerState::Halted | ComputerState::Faulted(_)
+        )
+    }
+
+    pub fn state(&self) -> &ComputerState {
+        &self.state
+    }
 
-        if let WaitingForInput(param) = self.state {
-            self.state = Running;
-            self.take_input(&param);
+    pub fn pending_input(&self) -> &[isize] {
+        &self.input
+    }
+
+    pub fn pending_output(&self) -> &[isize] {
+        &self.output
+    }
+
+    pub fn instruction_pointer(&self) -> usize {
+        self.instruction_pointer
+    }
+
+    pub fn relative_base(&self) -> isize {
+        self.relative_base
+    }
+
+    pub fn steps(&self) -> usize {
+        self.counters.opcodes.iter().sum()
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            instructions: (0..)
+                .zip(self.counters.opcodes.iter().cloned())
+                .filter(|(_, count)| *count > 0)
+                .collect(),
+            inputs: self.counters.inputs,
+            outputs: self.counters.outputs,
+            highest_address: self.counters.highest_address.get(),
+            extended_memory_cells: self.extended_memory.len(),
+            min_relative_base: self.counters.min_relative_base,
+            max_relative_base: self.counters.max_relative_base,
+            wall_time: self.counters.wall_time,
         }
+    }
 
-        while self.perform_more() {
-            self.tick();
+    pub fn first_cell(&self) -> isize {
+        self.get_value(0)
+    }
+
+    pub fn set_first_cell(&mut self, value: isize) -> Result<(), MemoryError> {
+        self.poke(0, value)
+    }
+
+    pub fn peek(&self, address: isize) -> Result<isize, MemoryError> {
+        Ok(self.get_value(self.resolve(address)?))
+    }
+
+    pub fn poke(&mut self, address: isize, value: isize) -> Result<(), MemoryError> {
+        self.set_value(self.resolve(address)?, value)
+    }
+
+    pub fn snapshot(&self) -> MemorySnapshot {
+        MemorySnapshot::new(&self.instructions, &self.extended_memory)
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            instructions: self.instructions.clone(),
+            extended_memory: self.extended_memory.clone(),
+            instruction_pointer: self.instruction_pointer,
+            relative_base: self.relative_base,
+            state: self.state.clone(),
+            input: self.input.clone(),
+            output: self.output.clone(),
         }
+    }
+
+    pub fn restore(&mut self, checkpoint: &Checkpoint) -> &mut Self {
+        self.instructions = checkpoint.instructions.clone();
+        self.extended_memory = checkpoint.extended_memory.clone();
+        self.instruction_pointer = checkpoint.instruction_pointer;
+        self.relative_base = checkpoint.relative_base;
+        self.state = checkpoint.state.clone();
+        self.input = checkpoint.input.clone();
+        self.output = checkpoint.output.clone();
+        self
+    }
+
+    // Extensions and observers are owned by the original computer and are not carried over
+    pub fn fork(&self) -> Self {
+        if !self.extensions.is_empty() {
+            panic!("Cannot fork a computer with extensions");
+        }
+
+        let mut fork = Self::new(&vec![]);
+        fork.restore(&self.checkpoint())
+            .set_address_policy(self.address_policy);
+        fork
+    }
+
+    fn resolve(&self, address: isize) -> Result<usize, MemoryError> {
+        let policy = &self.address_policy;
+        let resolved = match (address < 0, policy.negative_addresses) {
+            (false, _) => address as usize,
+            (true, NegativeAddresses::Error) => return Err(MemoryError::NegativeAddress(address)),
+            (true, NegativeAddresses::Wrap) => {
+                (address as i128).rem_euclid(policy.max_address as i128 + 1) as usize
+            }
+        };
+
+        if resolved > policy.max_address {
+            return Err(MemoryError::AddressTooHigh(resolved));
+        }
+        Ok(resolved)
+    }
+
+    pub fn run(&mut self) -> StopReason {
+        self.run_until_input()
+    }
 
-        self.instructions[0]
+    pub fn step(&mut self) -> StopReason {
+        self.timed(Self::advance)
     }
 
-    fn perform_more(&self) -> bool {
+    pub fn run_until_output(&mut self, count: usize) -> StopReason {
+        let expected = self.output.len() + count;
+
+        self.timed(|computer| loop {
+            if computer.output.len() >= expected {
+                return StopReason::OutputProduced;
+            }
+            match computer.advance() {
+                StopReason::Stepped => continue,
+                reason => return reason,
+            }
+        })
+    }
+
+    pub fn run_until_input(&mut self) -> StopReason {
+        self.timed(|computer| loop {
+            match computer.advance() {
+                StopReason::Stepped => continue,
+                reason => return reason,
+            }
+        })
+    }
+
+    fn timed<F>(&mut self, action: F) -> StopReason
+    where
+        F: FnOnce(&mut Self) -> StopReason,
+    {
+        let started = Instant::now();
+        let reason = action(self);
+        self.counters.wall_time += started.elapsed();
+        reason
+    }
+
+    fn advance(&mut self) -> StopReason {
         use ComputerState::*;
 
+        let (address, result) = match self.state {
+            Halted => return StopReason::Halted,
+            Faulted(fault) => return StopReason::Fault(fault),
+            WaitingForInput(_) if self.input.is_empty() => return StopReason::WaitingForInput,
+            WaitingForInput(param) => {
+                self.state = Running;
+                // The input instruction and its parameter have been read already
+                let address = self.instruction_pointer - 2;
+                let result = self.take_input(&param).map_err(FaultError::from);
+                if result.is_ok() {
+                    self.notify(|observer, computer| {
+                        observer.instruction_executed(computer, address, &Instruction::Input(param))
+                    });
+                }
+                (address, result)
+            }
+            Initialized | Running => {
+                self.state = Running;
+                (self.instruction_pointer, self.tick())
+            }
+        };
+
+        if let Err(error) = result {
+            let fault = Fault { ip: address, error };
+            self.instruction_pointer = address;
+            self.state = Faulted(fault);
+            return StopReason::Fault(fault);
+        }
+
         match self.state {
-            WaitingForInput(_) | Halted => false,
-            _ => true,
+            WaitingForInput(_) => StopReason::WaitingForInput,
+            Halted => StopReason::Halted,
+            _ => StopReason::Stepped,
         }
     }
 
-    fn get(&mut self) -> isize {
-        let result = self.get_value(self.instruction_pointer);
+    fn get(&mut self) -> Result<isize, MemoryError> {
+        let address = self.resolve(self.instruction_pointer as isize)?;
+        self.touch(address);
         self.instruction_pointer += 1;
-        result
+        Ok(self.get_value(address))
     }
 
-    fn tick(&mut self) {
+    fn tick(&mut self) -> Result<(), FaultError> {
         use Instruction::*;
 
-        let instruction = Instruction::next(self);
-        if self.debug {
-            println!("!!== {:?} == !!", self.name);
-            println!("Got instruction: {:?}", instruction);
-        }
+        let address = self.instruction_pointer;
+        let instruction = Instruction::next(self)?;
+        self.counters.opcodes[instruction.opcode() as usize] += 1;
 
-        match instruction {
-            Sum(params) => self.opcode_with_3_args(&params, |a, b| a + b),
-            Mul(params) => self.opcode_with_3_args(&params, |a, b| a * b),
-            Input(param) => self.take_input(&param),
-            Output(param) => self.put_output(self.value_for_param(&param)),
+        match &instruction {
+            Sum(params) => self.opcode_with_3_args(params, |a, b| a + b)?,
+            Mul(params) => self.opcode_with_3_args(params, |a, b| a * b)?,
+            Input(param) => self.take_input(param)?,
+            Output(param) => self.put_output(self.value_for_param(param)?),
             JmpIfTrue(params) => {
-                if self.value_for_param(&params[0]) != 0 {
-                    self.set_pointer(self.value_for_param(&params[1]) as usize);
+                if self.value_for_param(&params[0])? != 0 {
+                    self.set_pointer(self.value_for_param(&params[1])?)?;
                 }
             }
             JmpIfFalse(params) => {
-                if self.value_for_param(&params[0]) == 0 {
-                    self.set_pointer(self.value_for_param(&params[1]) as usize);
+                if self.value_for_param(&params[0])? == 0 {
+                    self.set_pointer(self.value_for_param(&params[1])?)?;
                 }
             }
             LessThan(params) => {
-                if self.value_for_param(&params[0]) < self.value_for_param(&params[1]) {
-                    self.set_value_from_param(&params[2], 1)
+                if self.value_for_param(&params[0])? < self.value_for_param(&params[1])? {
+                    self.set_value_from_param(&params[2], 1)?
                 } else {
-                    self.set_value_from_param(&params[2], 0)
+                    self.set_value_from_param(&params[2], 0)?
                 }
             }
             Equal(params) => {
-                if self.value_for_param(&params[0]) == self.value_for_param(&params[1]) {
-                    self.set_value_from_param(&params[2], 1)
+                if self.value_for_param(&params[0])? == self.value_for_param(&params[1])? {
+                    self.set_value_from_param(&params[2], 1)?
                 } else {
-                    self.set_value_from_param(&params[2], 0)
+                    self.set_value_from_param(&params[2], 0)?
                 }
             }
-            SetRelBase(param) => self.relative_base += self.value_for_param(&param),
+            SetRelBase(param) => {
+                self.relative_base = self
+                    .relative_base
+                    .checked_add(self.value_for_param(param)?)
+                    .ok_or(MemoryError::AddressOverflow)?;
+                self.counters.min_relative_base =
+                    isize::min(self.counters.min_relative_base, self.relative_base);
+                self.counters.max_relative_base =
+                    isize::max(self.counters.max_relative_base, self.relative_base);
+            }
+            Extension(opcode, params) => self.call_extension(*opcode, params)?,
             Halt => self.state = ComputerState::Halted,
         }
-        if self.debug {
-            println!("instructions: {:?}", self.instructions);
-            println!("instruction_pointer: {:?}", self.instruction_pointer);
-            println!("extended_memory: {:?}", self.extended_memory);
-            println!("state: {:?}", self.state);
-            println!("input: {:?}", self.input);
-            println!("output: {:?}", self.output);
-            println!("relative_base: {:?}", self.relative_base);
-            println!("=====================================================================");
-            println!("");
-            sleep(Duration::from_millis(2000 as u64));
+
+        // An input instruction that has to wait is reported once the input arrives
+        if !matches!(self.state, ComputerState::WaitingForInput(_)) {
+            self.notify(|observer, computer| {
+                observer.instruction_executed(computer, address, &instruction)
+            });
+        }
+        if self.halted() {
+            self.notify(|observer, computer| observer.halted(computer));
         }
+        Ok(())
     }
 
-    fn set_pointer(&mut self, address: usize) {
-        if self.debug {
-            println!("Setting pointer to {}", address);
+    fn notify<F>(&mut self, mut event: F)
+    where
+        F: FnMut(&mut dyn Observer, &Self),
+    {
+        if self.observers.is_empty() {
+            return;
+        }
+
+        let mut observers = mem::take(&mut self.observers);
+        for observer in observers.iter_mut() {
+            event(observer.as_mut(), self);
         }
-        self.instruction_pointer = address;
+        self.observers = observers;
     }
 
-    fn value_for_param(&self, param: &Param) -> isize {
+    fn set_pointer(&mut self, address: isize) -> Result<(), MemoryError> {
+        self.instruction_pointer = self.resolve(address)?;
+        Ok(())
+    }
+
+    fn param_address(&self, param: &Param) -> Result<usize, MemoryError> {
+        let address = match param.mode {
+            ParamMode::Positional => self.resolve(param.value)?,
+            ParamMode::Immidiate => panic!("Immidiate mode parameter has no address"),
+            ParamMode::Relative => self.resolve(
+                param
+                    .value
+                    .checked_add(self.relative_base)
+                    .ok_or(MemoryError::AddressOverflow)?,
+            )?,
+        };
+        self.touch(address);
+        Ok(address)
+    }
+
+    fn value_for_param(&self, param: &Param) -> Result<isize, MemoryError> {
         match param.mode {
-            ParamMode::Positional => self.get_value(param.value as usize),
-            ParamMode::Immidiate => param.value,
-            ParamMode::Relative => self.get_value((param.value + self.relative_base) as usize),
+            ParamMode::Immidiate => Ok(param.value),
+            _ => Ok(self.get_value(self.param_address(param)?)),
         }
     }
 
+    fn touch(&self, address: usize) {
+        let highest = &self.counters.highest_address;
+        highest.set(usize::max(highest.get(), address));
+    }
+
     fn get_value(&self, address: usize) -> isize {
-        let result = if self.extended_memory_address(address) {
+        if self.extended_memory_address(address) {
             self.extended_memory.get(&address).unwrap_or(&0).clone()
         } else {
             self.instructions[address]
-        };
-        if self.debug {
-            println!("Got value {} at {}", result, address);
         }
-        result
     }
 
-    fn set_value(&mut self, address: usize, value: isize) {
-        if self.debug {
-            println!("Going to set {} to {}", address, value);
-        }
+    fn set_value(&mut self, address: usize, value: isize) -> Result<(), MemoryError> {
         if self.extended_memory_address(address) {
+            let new_cell = !self.extended_memory.contains_key(&address);
+            if new_cell && self.extended_memory.len() >= self.address_policy.max_extended_cells {
+                return Err(MemoryError::ExtendedMemoryExhausted(address));
+            }
             self.extended_memory.insert(address, value);
         } else {
             self.instructions[address] = value;
         }
+        self.notify(|observer, computer| observer.memory_written(computer, address, value));
+        Ok(())
     }
 
     fn extended_memory_address(&self, address: usize) -> bool {
         address >= self.instructions.len()
     }
 
-    fn opcode_with_3_args(&mut self, params: &[Param; 3], f: fn(isize, isize) -> isize) {
+    fn opcode_with_3_args(
+        &mut self,
+        params: &[Param; 3],
+        f: fn(isize, isize) -> isize,
+    ) -> Result<(), MemoryError> {
         let [p1, p2, p3] = params;
-        let val1 = self.value_for_param(&p1);
-        let val2 = self.value_for_param(&p2);
-        self.set_value_from_param(&p3, f(val1, val2));
+        let val1 = self.value_for_param(&p1)?;
+        let val2 = self.value_for_param(&p2)?;
+        self.set_value_from_param(&p3, f(val1, val2))
     }
 
-    fn set_value_from_param(&mut self, param: &Param, value: isize) {
-        match param.mode {
-            ParamMode::Positional => self.set_value(param.value as usize, value),
-            ParamMode::Immidiate => panic!("It's impossible to use immidiate mode to set value"),
-            ParamMode::Relative => {
-                self.set_value((param.value + self.relative_base) as usize, value);
-            }
+    fn set_value_from_param(&mut self, param: &Param, value: isize) -> Result<(), MemoryError> {
+        if param.mode == ParamMode::Immidiate {
+            panic!("It's impossible to use immidiate mode to set value");
         }
+        self.set_value(self.param_address(param)?, value)
     }
 
-    fn take_input(&mut self, param: &Param) {
+    fn take_input(&mut self, param: &Param) -> Result<(), MemoryError> {
         if self.input.is_empty() {
             self.state = ComputerState::WaitingForInput(*param);
-            return;
+            self.notify(|observer, computer| observer.input_requested(computer));
+            return Ok(());
         }
-        let input = self.input.remove(0);
-        self.set_value_from_param(&param, input);
+        self.set_value_from_param(&param, self.input[0])?;
+        self.input.remove(0);
+        self.counters.inputs += 1;
+        Ok(())
     }
 
     fn put_output(&mut self, value: isize) {
         self.output.push(value);
+        self.counters.outputs += 1;
+        self.notify(|observer, computer| observer.output_produced(computer, value));
+    }
+
+    fn call_extension(&mut self, opcode: isize, params: &[Param]) -> Result<(), MemoryError> {
+        let mut extension = self.extensions.remove(&opcode).unwrap();
+        let mut call = ExtensionCall {
+            computer: self,
+            params,
+        };
+        let result = (extension.callback)(&mut call);
+        self.extensions.insert(opcode, extension);
+
+        result
+    }
+}
+
+type ExtensionCallback = Box<dyn FnMut(&mut ExtensionCall) -> Result<(), MemoryError>>;
+
+struct Extension {
+    params: usize,
+    callback: ExtensionCallback,
+}
+
+impl fmt::Debug for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Extension")
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+pub struct ExtensionCall<'a> {
+    computer: &'a mut OpcodeComputer,
+    params: &'a [Param],
+}
+
+impl ExtensionCall<'_> {
+    pub fn params(&self) -> &[Param] {
+        self.params
+    }
+
+    // Memory errors returned from the callback fault the computer at the extension opcode
+    pub fn value(&self, index: usize) -> Result<isize, MemoryError> {
+        self.computer.value_for_param(&self.params[index])
+    }
+
+    pub fn set_value(&mut self, index: usize, value: isize) -> Result<(), MemoryError> {
+        self.computer
+            .set_value_from_param(&self.params[index], value)
+    }
+
+    pub fn add_output(&mut self, value: isize) {
+        self.computer.put_output(value);
+    }
+}
+
+pub trait Observer {
+    fn instruction_executed(
+        &mut self,
+        _computer: &OpcodeComputer,
+        _address: usize,
+        _instruction: &Instruction,
+    ) {
+    }
+
+    fn memory_written(&mut self, _computer: &OpcodeComputer, _address: usize, _value: isize) {}
+
+    fn input_requested(&mut self, _computer: &OpcodeComputer) {}
+
+    fn input_added(&mut self, _computer: &OpcodeComputer, _value: isize) {}
+
+    fn output_produced(&mut self, _computer: &OpcodeComputer, _value: isize) {}
+
+    fn halted(&mut self, _computer: &OpcodeComputer) {}
+}
+
+impl fmt::Debug for dyn Observer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Observer")
+    }
+}
+
+impl<O: Observer> Observer for Rc<RefCell<O>> {
+    fn instruction_executed(
+        &mut self,
+        computer: &OpcodeComputer,
+        address: usize,
+        instruction: &Instruction,
+    ) {
+        self.borrow_mut()
+            .instruction_executed(computer, address, instruction);
+    }
+
+    fn memory_written(&mut self, computer: &OpcodeComputer, address: usize, value: isize) {
+        self.borrow_mut().memory_written(computer, address, value);
+    }
+
+    fn input_requested(&mut self, computer: &OpcodeComputer) {
+        self.borrow_mut().input_requested(computer);
+    }
+
+    fn input_added(&mut self, computer: &OpcodeComputer, value: isize) {
+        self.borrow_mut().input_added(computer, value);
+    }
+
+    fn output_produced(&mut self, computer: &OpcodeComputer, value: isize) {
+        self.borrow_mut().output_produced(computer, value);
+    }
+
+    fn halted(&mut self, computer: &OpcodeComputer) {
+        self.borrow_mut().halted(computer);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DebugObserver {
+    name: Option<String>,
+}
+
+impl DebugObserver {
+    pub fn named(name: &str) -> Self {
+        Self {
+            name: Some(name.into()),
+        }
+    }
+}
+
+impl Observer for DebugObserver {
+    fn instruction_executed(
+        &mut self,
+        computer: &OpcodeComputer,
+        address: usize,
+        instruction: &Instruction,
+    ) {
+        println!("!!== {:?} == !!", self.name);
+        println!("Got instruction at {}: {:?}", address, instruction);
+        println!("instructions: {:?}", computer.instructions);
+        println!("instruction_pointer: {:?}", computer.instruction_pointer);
+        println!("extended_memory: {:?}", computer.extended_memory);
+        println!("state: {:?}", computer.state);
+        println!("input: {:?}", computer.input);
+        println!("output: {:?}", computer.output);
+        println!("relative_base: {:?}", computer.relative_base);
+        println!("=====================================================================");
+        println!();
+        sleep(Duration::from_millis(2000));
+    }
+
+    fn memory_written(&mut self, _computer: &OpcodeComputer, address: usize, value: isize) {
+        println!("Set {} to {}", address, value);
     }
 }
 
@@ -245,10 +850,35 @@ impl Param {
     pub fn new(value: isize, mode: ParamMode) -> Self {
         Self { value, mode }
     }
+
+    pub fn value(&self) -> isize {
+        self.value
+    }
+
+    pub fn mode(&self) -> ParamMode {
+        self.mode
+    }
+}
+
+// Name and parameter count of each builtin opcode
+pub fn mnemonic(opcode: isize) -> Option<(&'static str, usize)> {
+    match opcode {
+        1 => Some(("ADD", 3)),
+        2 => Some(("MUL", 3)),
+        3 => Some(("IN", 1)),
+        4 => Some(("OUT", 1)),
+        5 => Some(("JNZ", 2)),
+        6 => Some(("JZ", 2)),
+        7 => Some(("LT", 3)),
+        8 => Some(("EQ", 3)),
+        9 => Some(("ARB", 1)),
+        99 => Some(("HLT", 0)),
+        _ => None,
+    }
 }
 
 #[derive(Debug)]
-enum Instruction {
+pub enum Instruction {
     Sum([Param; 3]),
     Mul([Param; 3]),
     Input(Param),
@@ -258,49 +888,87 @@ enum Instruction {
     LessThan([Param; 3]),
     Equal([Param; 3]),
     SetRelBase(Param),
+    Extension(isize, Vec<Param>),
     Halt,
 }
 
 impl Instruction {
-    pub fn next(program: &mut OpcodeComputer) -> Self {
+    fn next(program: &mut OpcodeComputer) -> Result<Self, FaultError> {
         use Instruction::*;
-        let instruction = program.get();
+        let instruction = program.get()?;
 
-        match Instruction::parse(instruction) {
+        let instruction = match Instruction::parse(instruction) {
             (1, [m1, m2, m3]) => Sum([
-                Param::new(program.get(), m1),
-                Param::new(program.get(), m2),
-                Param::new(program.get(), m3),
+                Param::new(program.get()?, m1),
+                Param::new(program.get()?, m2),
+                Param::new(program.get()?, m3),
             ]),
             (2, [m1, m2, m3]) => Mul([
-                Param::new(program.get(), m1),
-                Param::new(program.get(), m2),
-                Param::new(program.get(), m3),
+                Param::new(program.get()?, m1),
+                Param::new(program.get()?, m2),
+                Param::new(program.get()?, m3),
+            ]),
+            (3, [m, _, _]) => Input(Param::new(program.get()?, m)),
+            (4, [m, _, _]) => Output(Param::new(program.get()?, m)),
+            (5, [m1, m2, _]) => JmpIfTrue([
+                Param::new(program.get()?, m1),
+                Param::new(program.get()?, m2),
+            ]),
+            (6, [m1, m2, _]) => JmpIfFalse([
+                Param::new(program.get()?, m1),
+                Param::new(program.get()?, m2),
             ]),
-            (3, [m, _, _]) => Input(Param::new(program.get(), m)),
-            (4, [m, _, _]) => Output(Param::new(program.get(), m)),
-            (5, [m1, m2, _]) => {
-                JmpIfTrue([Param::new(program.get(), m1), Param::new(program.get(), m2)])
-            }
-            (6, [m1, m2, _]) => {
-                JmpIfFalse([Param::new(program.get(), m1), Param::new(program.get(), m2)])
-            }
             (7, [m1, m2, m3]) => LessThan([
-                Param::new(program.get(), m1),
-                Param::new(program.get(), m2),
-                Param::new(program.get(), m3),
+                Param::new(program.get()?, m1),
+                Param::new(program.get()?, m2),
+                Param::new(program.get()?, m3),
             ]),
             (8, [m1, m2, m3]) => Equal([
-                Param::new(program.get(), m1),
-                Param::new(program.get(), m2),
-                Param::new(program.get(), m3),
+                Param::new(program.get()?, m1),
+                Param::new(program.get()?, m2),
+                Param::new(program.get()?, m3),
             ]),
-            (9, [m1, _, _]) => SetRelBase(Param::new(program.get(), m1)),
+            (9, [m1, _, _]) => SetRelBase(Param::new(program.get()?, m1)),
             (99, _) => Halt,
-            _ => unreachable!("Wrong instruction {}!", instruction),
+            (opcode, _) if program.extensions.contains_key(&opcode) => {
+                let count = program.extensions[&opcode].params;
+                let params = (0..count)
+                    .map(|position| {
+                        let mode =
+                            ParamMode::new((instruction / 10_isize.pow(position as u32 + 2)) % 10);
+                        Ok(Param::new(program.get()?, mode))
+                    })
+                    .collect::<Result<_, MemoryError>>()?;
+                Extension(opcode, params)
+            }
+            (opcode, _) => return Err(FaultError::UnknownOpcode(opcode)),
+        };
+
+        Ok(instruction)
+    }
+
+    fn opcode(&self) -> isize {
+        use Instruction::*;
+
+        match self {
+            Sum(_) => 1,
+            Mul(_) => 2,
+            Input(_) => 3,
+            Output(_) => 4,
+            JmpIfTrue(_) => 5,
+            JmpIfFalse(_) => 6,
+            LessThan(_) => 7,
+            Equal(_) => 8,
+            SetRelBase(_) => 9,
+            Extension(opcode, _) => *opcode,
+            Halt => 99,
         }
     }
 
+    fn is_builtin(opcode: isize) -> bool {
+        (1..=9).contains(&opcode) || opcode == 99
+    }
+
     fn parse(code: isize) -> (isize, [ParamMode; 3]) {
         (
             code % 100,
@@ -429,4 +1097,485 @@ mod tests {
 
         assert_eq!(computer.get_output(), Some(1125899906842624));
     }
+
+    #[test]
+    fn calls_registered_extension() {
+        let mut computer = OpcodeComputer::new(&vec![21050, 7, 3, 0, 4, 0, 99, 5]);
+        computer
+            .register_extension(50, 3, |call| {
+                let value = call.value(0)? * 10 + call.value(1)?;
+                call.set_value(2, value)
+            })
+            .unwrap();
+        computer.run();
+
+        assert_eq!(computer.get_output(), Some(53));
+    }
+
+    #[test]
+    fn extension_can_produce_output() {
+        let mut computer = OpcodeComputer::new(&vec![151, 42, 99]);
+        computer
+            .register_extension(51, 1, |call| {
+                let value = call.value(0)?;
+                call.add_output(value);
+                call.add_output(-value);
+                Ok(())
+            })
+            .unwrap();
+        computer.run();
+
+        assert_eq!(computer.get_all_output(), vec![42, -42]);
+    }
+
+    #[test]
+    fn extension_keeps_its_state_between_calls() {
+        let mut computer = OpcodeComputer::new(&vec![52, 0, 52, 0, 52, 0, 4, 0, 99]);
+        let mut counter = 100;
+        computer
+            .register_extension(52, 1, move |call| {
+                counter += 1;
+                call.set_value(0, counter)
+            })
+            .unwrap();
+        computer.run();
+
+        assert_eq!(computer.get_output(), Some(103));
+    }
+
+    #[test]
+    fn faults_on_memory_error_in_extension() {
+        let mut computer = OpcodeComputer::new(&vec![104, 1, 50, -3, 99]);
+        computer
+            .register_extension(50, 1, |call| {
+                let value = call.value(0)?;
+                call.add_output(value);
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(
+            computer.run(),
+            StopReason::Fault(Fault {
+                ip: 2,
+                error: FaultError::Memory(MemoryError::NegativeAddress(-3))
+            })
+        );
+        assert_eq!(computer.get_all_output(), vec![1]);
+    }
+
+    #[test]
+    fn faults_on_relative_address_overflow() {
+        let mut computer = OpcodeComputer::new(&vec![109, isize::MAX, 204, 1, 99]);
+
+        assert_eq!(
+            computer.run(),
+            StopReason::Fault(Fault {
+                ip: 2,
+                error: FaultError::Memory(MemoryError::AddressOverflow)
+            })
+        );
+    }
+
+    #[test]
+    fn steps_one_instruction_at_a_time() {
+        let mut computer = OpcodeComputer::new(&vec![1101, 2, 3, 0, 104, 5, 99]);
+
+        assert_eq!(computer.step(), StopReason::Stepped);
+        assert_eq!(computer.first_cell(), 5);
+        assert_eq!(computer.step(), StopReason::Stepped);
+        assert_eq!(computer.get_output(), Some(5));
+        assert_eq!(computer.step(), StopReason::Halted);
+        assert_eq!(computer.step(), StopReason::Halted);
+    }
+
+    #[test]
+    fn runs_until_requested_number_of_outputs() {
+        let mut computer = OpcodeComputer::new(&vec![104, 1, 104, 2, 104, 3, 99]);
+
+        assert_eq!(computer.run_until_output(2), StopReason::OutputProduced);
+        assert_eq!(computer.get_all_output(), vec![1, 2]);
+        assert_eq!(computer.run_until_output(2), StopReason::Halted);
+        assert_eq!(computer.get_all_output(), vec![3]);
+    }
+
+    #[test]
+    fn runs_until_input_is_required() {
+        let mut computer = OpcodeComputer::new(&vec![3, 0, 104, 1, 3, 0, 99]);
+
+        assert_eq!(computer.run_until_input(), StopReason::WaitingForInput);
+        assert_eq!(computer.step(), StopReason::WaitingForInput);
+        assert_eq!(
+            computer.add_input(&1).run_until_input(),
+            StopReason::WaitingForInput
+        );
+        assert_eq!(computer.get_output(), Some(1));
+        assert_eq!(computer.add_input(&2).run_until_input(), StopReason::Halted);
+        assert_eq!(*computer.state(), ComputerState::Halted);
+    }
+
+    #[test]
+    fn sets_first_cell() {
+        let mut computer = OpcodeComputer::new(&vec![1, 0, 0, 0, 99]);
+        computer.set_first_cell(2).unwrap();
+        computer.run();
+
+        assert_eq!(computer.first_cell(), 4);
+    }
+
+    #[test]
+    fn peeks_and_pokes_image_and_extended_memory() {
+        let mut computer = OpcodeComputer::new(&vec![1, 0, 0, 0, 99]);
+
+        assert_eq!(computer.peek(4), Ok(99));
+        assert_eq!(computer.peek(1000), Ok(0));
+        assert_eq!(computer.poke(1, 4), Ok(()));
+        assert_eq!(computer.poke(1000, 42), Ok(()));
+        computer.run();
+
+        assert_eq!(computer.peek(0), Ok(100));
+        assert_eq!(computer.peek(1000), Ok(42));
+    }
+
+    #[test]
+    fn refuses_negative_addresses() {
+        let mut computer = OpcodeComputer::new(&vec![99]);
+
+        assert_eq!(computer.peek(-1), Err(MemoryError::NegativeAddress(-1)));
+        assert_eq!(computer.poke(-5, 1), Err(MemoryError::NegativeAddress(-5)));
+    }
+
+    #[test]
+    fn faults_on_negative_address_with_faulting_ip() {
+        let mut computer = OpcodeComputer::new(&vec![104, 0, 1, -1, 0, 0, 99]);
+        let fault = Fault {
+            ip: 2,
+            error: FaultError::Memory(MemoryError::NegativeAddress(-1)),
+        };
+
+        assert_eq!(computer.run(), StopReason::Fault(fault));
+        assert_eq!(*computer.state(), ComputerState::Faulted(fault));
+        assert_eq!(computer.instruction_pointer(), 2);
+        assert_eq!(computer.get_all_output(), vec![0]);
+        assert_eq!(computer.step(), StopReason::Fault(fault));
+    }
+
+    #[test]
+    fn wraps_negative_addresses() {
+        let mut computer = OpcodeComputer::new(&vec![4, -1, 99]);
+        computer.set_address_policy(AddressPolicy {
+            max_address: 99,
+            negative_addresses: NegativeAddresses::Wrap,
+            ..AddressPolicy::default()
+        });
+
+        assert_eq!(computer.poke(-1, 7), Ok(()));
+        assert_eq!(computer.peek(99), Ok(7));
+        assert_eq!(computer.run(), StopReason::Halted);
+        assert_eq!(computer.get_output(), Some(7));
+    }
+
+    #[test]
+    fn faults_above_max_address() {
+        let policy = AddressPolicy {
+            max_address: 10,
+            ..AddressPolicy::default()
+        };
+        let mut writing = OpcodeComputer::new(&vec![1101, 1, 1, 20, 99]);
+        writing.set_address_policy(policy);
+        let mut jumping = OpcodeComputer::new(&vec![104, 1, 1105, 1, 50]);
+        jumping.set_address_policy(policy);
+
+        assert_eq!(
+            writing.run(),
+            StopReason::Fault(Fault {
+                ip: 0,
+                error: FaultError::Memory(MemoryError::AddressTooHigh(20))
+            })
+        );
+        assert_eq!(
+            jumping.run(),
+            StopReason::Fault(Fault {
+                ip: 2,
+                error: FaultError::Memory(MemoryError::AddressTooHigh(50))
+            })
+        );
+        assert_eq!(writing.peek(11), Err(MemoryError::AddressTooHigh(11)));
+    }
+
+    #[test]
+    fn limits_touched_extended_memory_cells() {
+        let mut computer =
+            OpcodeComputer::new(&vec![1101, 1, 1, 100, 1101, 2, 2, 100, 1101, 3, 3, 101, 99]);
+        computer.set_address_policy(AddressPolicy {
+            max_extended_cells: 1,
+            ..AddressPolicy::default()
+        });
+
+        assert_eq!(
+            computer.run(),
+            StopReason::Fault(Fault {
+                ip: 8,
+                error: FaultError::Memory(MemoryError::ExtendedMemoryExhausted(101))
+            })
+        );
+        assert_eq!(computer.peek(100), Ok(4));
+        assert_eq!(
+            computer.poke(102, 1),
+            Err(MemoryError::ExtendedMemoryExhausted(102))
+        );
+    }
+
+    #[test]
+    fn faults_on_resumed_input() {
+        let mut computer = OpcodeComputer::new(&vec![104, 0, 3, -1, 99]);
+
+        assert_eq!(computer.run(), StopReason::WaitingForInput);
+        assert_eq!(
+            computer.add_input(&1).run(),
+            StopReason::Fault(Fault {
+                ip: 2,
+                error: FaultError::Memory(MemoryError::NegativeAddress(-1))
+            })
+        );
+    }
+
+    #[test]
+    fn captures_image_and_extended_memory() {
+        let mut computer = OpcodeComputer::new(&vec![1101, 2, 3, 10, 99]);
+        computer.run();
+        let snapshot = computer.snapshot();
+
+        assert_eq!(snapshot.get(0), 1101);
+        assert_eq!(snapshot.get(10), 5);
+        assert_eq!(snapshot.get(11), 0);
+        assert_eq!(
+            snapshot.addresses().collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4, 10]
+        );
+    }
+
+    #[test]
+    fn diffs_two_snapshots() {
+        let mut computer = OpcodeComputer::new(&vec![
+            3, 22, 1, 20, 22, 20, 1002, 20, 2, 21, 4, 20, 1105, 1, 0, 0, 0, 0, 0, 0, 7, 0, 0,
+        ]);
+        let before = computer.snapshot();
+        computer.add_input(&3).run();
+        let after = computer.snapshot();
+
+        let expected = vec![
+            CellChange {
+                address: 20,
+                before: 7,
+                after: 10,
+            },
+            CellChange {
+                address: 21,
+                before: 0,
+                after: 20,
+            },
+            CellChange {
+                address: 22,
+                before: 0,
+                after: 3,
+            },
+        ];
+        assert_eq!(before.diff(&after), expected);
+    }
+
+    #[test]
+    fn restores_checkpoint() {
+        let mut computer = OpcodeComputer::new(&vec![3, 9, 1001, 9, 1, 9, 4, 9, 99, 0]);
+        computer.add_input(&5).step();
+        let checkpoint = computer.checkpoint();
+        computer.run();
+        computer.poke(100, 1).unwrap();
+
+        assert_eq!(computer.restore(&checkpoint).run(), StopReason::Halted);
+        assert_eq!(computer.get_all_output(), vec![6]);
+        assert_eq!(computer.peek(100), Ok(0));
+    }
+
+    #[test]
+    fn forks_independent_computer() {
+        let mut computer = OpcodeComputer::new(&vec![3, 9, 1001, 9, 1, 9, 4, 9, 99, 0]);
+        computer.add_input(&5).step();
+        let mut fork = computer.fork();
+        computer.run();
+        computer.poke(9, 0).unwrap();
+
+        fork.run();
+        assert_eq!(fork.get_all_output(), vec![6]);
+        assert_eq!(fork.peek(9), Ok(6));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot fork a computer with extensions")]
+    fn refuses_to_fork_extended_computer() {
+        let mut computer = OpcodeComputer::new(&vec![99]);
+        computer
+            .register_extension(50, 0, |_| Ok(()))
+            .unwrap()
+            .fork();
+    }
+
+    #[test]
+    fn counts_executed_instructions() {
+        let mut computer = OpcodeComputer::new(&vec![3, 0, 1001, 0, 1, 0, 4, 0, 104, 7, 99]);
+        computer.add_input(&5).run();
+        let stats = computer.stats();
+
+        let expected: BTreeMap<isize, usize> =
+            vec![(1, 1), (3, 1), (4, 2), (99, 1)].into_iter().collect();
+        assert_eq!(stats.instructions, expected);
+        assert_eq!(stats.total_instructions(), 5);
+        assert_eq!(computer.steps(), 5);
+        assert_eq!(stats.inputs, 1);
+        assert_eq!(stats.outputs, 2);
+        assert_eq!(stats.highest_address, 10);
+    }
+
+    #[test]
+    fn tracks_relative_base_and_extended_memory() {
+        let program = vec![109, 50, 21101, 1, 2, 0, 109, -60, 22101, 0, 60, 70, 99];
+        let mut computer = OpcodeComputer::new(&program);
+        computer.run();
+        let stats = computer.stats();
+
+        assert_eq!(stats.min_relative_base, -10);
+        assert_eq!(stats.max_relative_base, 50);
+        assert_eq!(stats.highest_address, 60);
+        assert_eq!(stats.extended_memory_cells, 2);
+    }
+
+    #[test]
+    fn does_not_count_peeks_and_pokes() {
+        let mut computer = OpcodeComputer::new(&vec![99]);
+        computer.poke(100, 1).unwrap();
+        computer.peek(200).unwrap();
+        computer.run();
+
+        assert_eq!(computer.stats().highest_address, 0);
+        assert_eq!(computer.stats().total_instructions(), 1);
+    }
+
+    #[test]
+    fn prints_stats() {
+        let mut computer = OpcodeComputer::new(&vec![104, 7, 99]);
+        computer.run();
+        let printed = computer.stats().to_string();
+
+        assert!(
+            printed.starts_with("instructions: 2\n   4 OUT 1\n  99 HLT 1\ninputs: 0\noutputs: 1\n")
+        );
+        assert!(printed.contains("relative base: 0..=0\nwall time: "));
+    }
+
+    #[derive(Default)]
+    struct EventLog {
+        events: Vec<String>,
+    }
+
+    impl Observer for EventLog {
+        fn instruction_executed(
+            &mut self,
+            _computer: &OpcodeComputer,
+            address: usize,
+            _instruction: &Instruction,
+        ) {
+            self.events.push(format!("executed {}", address));
+        }
+
+        fn memory_written(&mut self, _computer: &OpcodeComputer, address: usize, value: isize) {
+            self.events
+                .push(format!("written {} to {}", value, address));
+        }
+
+        fn input_requested(&mut self, _computer: &OpcodeComputer) {
+            self.events.push("input requested".into());
+        }
+
+        fn output_produced(&mut self, _computer: &OpcodeComputer, value: isize) {
+            self.events.push(format!("output {}", value));
+        }
+
+        fn halted(&mut self, _computer: &OpcodeComputer) {
+            self.events.push("halted".into());
+        }
+    }
+
+    #[derive(Default)]
+    struct OutputCounter {
+        outputs: usize,
+    }
+
+    impl Observer for OutputCounter {
+        fn output_produced(&mut self, _computer: &OpcodeComputer, _value: isize) {
+            self.outputs += 1;
+        }
+    }
+
+    #[test]
+    fn notifies_observer_about_events() {
+        let log = Rc::new(RefCell::new(EventLog::default()));
+        let mut computer = OpcodeComputer::new(&vec![3, 0, 4, 0, 99]);
+        computer.add_observer(log.clone());
+
+        computer.run();
+        computer.add_input(&7).run();
+
+        assert_eq!(
+            log.borrow().events,
+            vec![
+                "input requested",
+                "written 7 to 0",
+                "executed 0",
+                "output 7",
+                "executed 2",
+                "executed 4",
+                "halted",
+            ]
+        );
+    }
+
+    #[test]
+    fn notifies_every_observer() {
+        let log = Rc::new(RefCell::new(EventLog::default()));
+        let counter = Rc::new(RefCell::new(OutputCounter::default()));
+        let mut computer = OpcodeComputer::new(&vec![104, 1, 104, 2, 99]);
+        computer
+            .add_observer(log.clone())
+            .add_observer(counter.clone());
+        computer.run();
+
+        assert_eq!(counter.borrow().outputs, 2);
+        assert_eq!(log.borrow().events.last().unwrap(), "halted");
+    }
+
+    #[test]
+    fn faults_on_unregistered_extension() {
+        let mut computer = OpcodeComputer::new(&vec![104, 1, 1050, 0, 99]);
+        let fault = Fault {
+            ip: 2,
+            error: FaultError::UnknownOpcode(50),
+        };
+
+        assert_eq!(computer.run(), StopReason::Fault(fault));
+        assert_eq!(*computer.state(), ComputerState::Faulted(fault));
+        assert_eq!(computer.get_all_output(), vec![1]);
+    }
+
+    #[test]
+    fn refuses_unavailable_extension_opcodes() {
+        let mut computer = OpcodeComputer::new(&vec![99]);
+
+        for opcode in &[2, 99, -1, 100] {
+            assert_eq!(
+                computer.register_extension(*opcode, 3, |_| Ok(())).err(),
+                Some(UnavailableOpcode { opcode: *opcode })
+            );
+        }
+        assert!(computer.register_extension(50, 3, |_| Ok(())).is_ok());
+    }
 }
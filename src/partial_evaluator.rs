@@ -0,0 +1,238 @@
+use crate::opcode_computer::{Checkpoint, OpcodeComputer, StopReason};
+
+const INPUT: isize = 3;
+const HALT: isize = 99;
+const STUB_CELLS: usize = 3;
+
+#[derive(Debug, PartialEq)]
+pub struct PartialEvaluation {
+    pub image: Vec<isize>,
+    pub resume_address: usize,
+    pub precomputed_instructions: usize,
+    checkpoint: Checkpoint,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StepLimitReached {
+    pub steps: usize,
+}
+
+impl PartialEvaluation {
+    // Continues from the first input with exactly the memory, relative base and pending
+    // output of the original run, without the stub of the image in memory
+    pub fn computer(&self) -> OpcodeComputer {
+        let mut computer = OpcodeComputer::new(&vec![]);
+        computer.restore(&self.checkpoint);
+        computer
+    }
+}
+
+// Runs the program until its first input instruction, a halt or a fault. Programs that
+// never get there, like ones looping forever, are given up after `max_steps`.
+//
+// The image starts with a jump to a stub appended after all the touched memory: it
+// restores the cells overwritten by that jump, sets the relative base, repeats the
+// output produced so far and jumps to the resumed instruction, or halts if the
+// program already did. The stub stays in memory after the touched cells.
+pub fn partially_evaluate(
+    program: &[isize],
+    max_steps: usize,
+) -> Result<PartialEvaluation, StepLimitReached> {
+    let mut computer = OpcodeComputer::new(&program.to_vec());
+    let mut steps = 0;
+
+    while !waits_for_input(&computer) {
+        if steps == max_steps {
+            return Err(StepLimitReached { steps: max_steps });
+        }
+        if computer.step() != StopReason::Stepped {
+            break;
+        }
+        steps += 1;
+    }
+
+    let resume_address = computer.instruction_pointer();
+    if steps == 0 {
+        return Ok(PartialEvaluation {
+            image: program.to_vec(),
+            resume_address,
+            precomputed_instructions: 0,
+            checkpoint: computer.checkpoint(),
+        });
+    }
+
+    let snapshot = computer.snapshot();
+    let length = snapshot
+        .addresses()
+        .max()
+        .map_or(0, |address| address + 1)
+        .max(STUB_CELLS);
+    let mut image: Vec<isize> = (0..length).map(|address| snapshot.get(address)).collect();
+
+    let mut stub = vec![];
+    for (address, value) in image.iter().take(STUB_CELLS).enumerate() {
+        stub.extend(&[1101, *value, 0, address as isize]);
+    }
+    if computer.relative_base() != 0 {
+        stub.extend(&[109, computer.relative_base()]);
+    }
+    for value in computer.pending_output() {
+        stub.extend(&[104, *value]);
+    }
+    if computer.halted() {
+        stub.push(HALT);
+    } else {
+        stub.extend(&[1105, 1, resume_address as isize]);
+    }
+
+    // Both jumps or the halt, the restored cells, the relative base and the output
+    let stub_instructions = 2
+        + STUB_CELLS
+        + usize::from(computer.relative_base() != 0)
+        + computer.pending_output().len();
+    image[..STUB_CELLS].copy_from_slice(&[1105, 1, length as isize]);
+    image.append(&mut stub);
+
+    Ok(PartialEvaluation {
+        image,
+        resume_address,
+        precomputed_instructions: steps.saturating_sub(stub_instructions),
+        checkpoint: computer.checkpoint(),
+    })
+}
+
+fn waits_for_input(computer: &OpcodeComputer) -> bool {
+    computer
+        .peek(computer.instruction_pointer() as isize)
+        .is_ok_and(|code| code % 100 == INPUT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEPS: usize = 10_000;
+
+    fn run(mut computer: OpcodeComputer, input: isize) -> Vec<isize> {
+        computer.add_input(&input).run();
+        computer.get_all_output()
+    }
+
+    fn run_program(program: &[isize], input: isize) -> Vec<isize> {
+        run(OpcodeComputer::new(&program.to_vec()), input)
+    }
+
+    #[test]
+    fn precomputes_setup_loop() {
+        // Sums 10..1 in a loop, then outputs input plus the sum
+        let program = vec![
+            1101, 0, 0, 28, 1101, 10, 0, 29, 1, 28, 29, 28, 1001, 29, -1, 29, 1005, 29, 8, 3, 30,
+            1, 30, 28, 30, 4, 30, 99, 0, 0, 0,
+        ];
+        let evaluation = partially_evaluate(&program, STEPS).unwrap();
+
+        assert_eq!(evaluation.resume_address, 19);
+        assert_eq!(evaluation.computer().instruction_pointer(), 19);
+        assert_eq!(evaluation.precomputed_instructions, 27);
+        assert_eq!(evaluation.computer().peek(28), Ok(55));
+        assert_eq!(evaluation.image[28], 55);
+        for input in &[0, 7, -100] {
+            assert_eq!(
+                run(evaluation.computer(), *input),
+                run_program(&program, *input)
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_relative_base_and_extended_memory() {
+        let program = vec![109, 20, 21101, 2, 3, 0, 203, 1, 204, 0, 204, 1, 99];
+        let evaluation = partially_evaluate(&program, STEPS).unwrap();
+        let computer = evaluation.computer();
+
+        assert_eq!(computer.instruction_pointer(), 6);
+        assert_eq!(computer.relative_base(), 20);
+        assert_eq!(computer.peek(20), Ok(5));
+        assert_eq!(run(computer, 9), vec![5, 9]);
+    }
+
+    #[test]
+    fn reads_untouched_cells_past_the_image() {
+        let program = vec![1101, 1, 1, 9, 3, 9, 4, 10, 99, 0];
+        let evaluation = partially_evaluate(&program, STEPS).unwrap();
+
+        assert_eq!(run_program(&program, 5), vec![0]);
+        assert_eq!(run(evaluation.computer(), 5), vec![0]);
+        assert_eq!(
+            evaluation.computer().snapshot(),
+            OpcodeComputer::new(&vec![1101, 1, 1, 9, 3, 9, 4, 10, 99, 2]).snapshot()
+        );
+    }
+
+    #[test]
+    fn keeps_output_produced_before_first_input() {
+        let program = vec![104, 7, 1101, 2, 2, 0, 3, 1, 4, 1, 4, 0, 99];
+        let evaluation = partially_evaluate(&program, STEPS).unwrap();
+
+        assert_eq!(evaluation.precomputed_instructions, 0);
+        assert_eq!(evaluation.computer().pending_output(), &[7]);
+        assert_eq!(run(evaluation.computer(), 3), vec![7, 3, 4]);
+    }
+
+    #[test]
+    fn precomputes_whole_program_without_input() {
+        let program = vec![1, 1, 1, 4, 99, 5, 6, 0, 99];
+        let evaluation = partially_evaluate(&program, STEPS).unwrap();
+        let mut computer = evaluation.computer();
+
+        assert!(computer.halted());
+        assert_eq!(computer.run(), StopReason::Halted);
+        assert_eq!(computer.first_cell(), 30);
+    }
+
+    #[test]
+    fn leaves_program_starting_with_input_untouched() {
+        let program = vec![3, 0, 4, 0, 99];
+        let evaluation = partially_evaluate(&program, STEPS).unwrap();
+
+        assert_eq!(evaluation.precomputed_instructions, 0);
+        assert_eq!(evaluation.image, program);
+        assert_eq!(
+            evaluation.computer().snapshot(),
+            OpcodeComputer::new(&program).snapshot()
+        );
+    }
+
+    #[test]
+    fn loads_image_into_fresh_computer() {
+        let programs = [
+            vec![
+                1101, 0, 0, 28, 1101, 10, 0, 29, 1, 28, 29, 28, 1001, 29, -1, 29, 1005, 29, 8, 3,
+                30, 1, 30, 28, 30, 4, 30, 99, 0, 0, 0,
+            ],
+            vec![109, 20, 21101, 2, 3, 0, 203, 1, 204, 0, 204, 1, 99],
+            vec![104, 7, 1101, 2, 2, 0, 3, 1, 4, 1, 4, 0, 99],
+            vec![1, 1, 1, 4, 99, 5, 6, 0, 99],
+            vec![3, 0, 4, 0, 99],
+        ];
+        for program in programs.iter() {
+            let evaluation = partially_evaluate(program, STEPS).unwrap();
+            for input in &[0, 7, -100] {
+                assert_eq!(
+                    run_program(&evaluation.image, *input),
+                    run_program(program, *input)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn gives_up_on_endless_loop() {
+        let program = vec![1101, 1, 1, 7, 1105, 1, 0, 0];
+
+        assert_eq!(
+            partially_evaluate(&program, 100),
+            Err(StepLimitReached { steps: 100 })
+        );
+    }
+}
@@ -1,6 +1,9 @@
-use std::{
-    fs::{self, File},
-    io::{BufRead, BufReader},
+use {
+    crate::{input_recording::Recording, memory_patch::Patch},
+    std::{
+        fs::{self, File},
+        io::{self, BufRead, BufReader},
+    },
 };
 
 pub fn read_lines(day_number: usize) -> Vec<String> {
@@ -11,10 +14,33 @@ pub fn read_lines(day_number: usize) -> Vec<String> {
 }
 
 pub fn read_intcode_program(day_number: usize) -> Vec<isize> {
-    fs::read_to_string(format!("inputs/input-{:02}.txt", day_number))
-        .unwrap()
+    try_read_intcode_program(day_number).unwrap()
+}
+
+// Like `read_intcode_program`, but a missing or unreadable input is an error naming the file
+pub fn try_read_intcode_program(day_number: usize) -> io::Result<Vec<isize>> {
+    let path = format!("inputs/input-{:02}.txt", day_number);
+    let text = fs::read_to_string(&path)
+        .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path, error)))?;
+
+    Ok(text
         .trim()
         .split(",")
         .map(|string| isize::from_str_radix(string.as_ref(), 10).unwrap())
-        .collect()
+        .collect())
+}
+
+pub fn read_patch(day_number: usize, name: &str) -> Patch {
+    let text = fs::read_to_string(format!("inputs/patches-{:02}.txt", day_number)).unwrap();
+
+    Patch::parse_all(&text)
+        .into_iter()
+        .find(|patch| patch.name == name)
+        .unwrap_or_else(|| panic!("There's no patch '{}' for day {}", name, day_number))
+}
+
+pub fn read_recording(day_number: usize) -> Recording {
+    Recording::parse(
+        &fs::read_to_string(format!("inputs/recording-{:02}.txt", day_number)).unwrap(),
+    )
 }